@@ -2,5 +2,16 @@
 //!
 //! `spatialize` is a collection of spatial data structures used for efficent
 //! spatial partitioning.
+//!
+//! The crate supports `no_std` environments with an allocator. Disable the default `std`
+//! feature to build it on top of `core` and `alloc`; `SyncQuadtree` and the `Error` impls
+//! are only available with `std`.
+//!
+//! Enable the `smallvec` feature to store the first few objects of every node inline, saving
+//! a heap allocation for each of the many nodes holding only one or two objects.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
 
 pub mod quadtree;
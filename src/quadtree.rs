@@ -1,30 +1,158 @@
-use std::cell::RefCell;
-use std::fmt::Debug;
-use std::rc::Rc;
+use alloc::boxed::Box;
+use alloc::collections::{BTreeMap, BTreeSet, BinaryHeap, VecDeque};
+use alloc::rc::{Rc, Weak};
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::cell::RefCell;
+use core::cmp::{Ordering, Reverse};
+use core::fmt::{self, Debug, Display, Write};
+
+use num_traits::Float;
+
+mod aabb;
+mod arena;
+#[cfg(feature = "bevy")]
+mod bevy_interop;
+mod builder;
+#[cfg(feature = "glam")]
+mod glam_interop;
+mod payload;
+mod point;
+#[cfg(feature = "serde")]
+mod serialization;
+#[cfg(feature = "std")]
+mod sync;
+
+pub use aabb::Aabb;
+pub use arena::ArenaQuadtree;
+#[cfg(feature = "bevy")]
+pub use bevy_interop::BroadPhase;
+pub use builder::QuadtreeBuilder;
+pub use payload::PayloadQuadtree;
+pub use point::Point;
+#[cfg(feature = "std")]
+pub use sync::{SharedBounded, SyncQuadtree};
+
+/// The capacity used by `Quadtree::new`. A capacity of `0` subdivides a node as soon as the
+/// first object is inserted into it.
+pub const DEFAULT_CAPACITY: usize = 0;
+
+/// The maximum depth used by `Quadtree::new` and `Quadtree::with_capacity`. Nodes at this depth
+/// no longer subdivide and keep every object inserted into them.
+pub const DEFAULT_MAX_DEPTH: usize = 16;
 
 /// A recursive data structure that divides a two-dimensional space into quadrants,
 /// used for efficient spatial partitioning of elements positioned in a 2D space.
+///
+/// The `Quadtree` is generic over the coordinate type `T`, which defaults to `f32`.
+///
+/// # Boundaries
+///
+/// The bounds of every node are inclusive, so an object touching the line between two
+/// quadrants fits both of them. Such an object is assigned to the lower quadrant, and then to
+/// the left one, i.e. quadrants are tried in the order southwest, southeast, northwest,
+/// northeast. Queries treat the bounds as inclusive as well and look into every quadrant
+/// touching the queried area, so objects on a boundary are always found.
+///
+/// ```
+/// # use std::rc::Rc;
+/// # use spatialize::quadtree::{Aabb, Bounded, Quadtree};
+/// let mut qt = Quadtree::with_limits(-10.0, 10.0, 20.0, 20.0, 0, 1);
+/// qt.insert_point(0.0, 5.0).unwrap();
+/// qt.insert_point(5.0, 0.0).unwrap();
+/// qt.insert_point(0.0, 0.0).unwrap();
+/// qt.insert(Rc::new(Aabb::new(-2.0, 0.0, 0.0, 2.0))).unwrap();
+/// assert_eq!(2, qt.northwest().unwrap().borrow().len());
+/// assert_eq!(1, qt.southeast().unwrap().borrow().len());
+/// assert_eq!(1, qt.southwest().unwrap().borrow().len());
+/// assert!(qt.northeast().unwrap().borrow().is_empty());
+///
+/// let mut hits: Vec<Rc<dyn Bounded>> = vec![];
+/// qt.query_point(0.0, 0.0, &mut hits);
+/// assert_eq!(2, hits.len());
+/// let mut on_line: Vec<Rc<dyn Bounded>> = vec![];
+/// qt.get_rect(&Aabb::new(0.0, 0.0, 10.0, 10.0), &mut on_line)
+///     .unwrap();
+/// assert_eq!(4, on_line.len());
+/// ```
+///
+/// # Examples
+/// ```
+/// use std::rc::Rc;
+/// use spatialize::quadtree::{Quadtree, Bounded};
+///
+/// #[derive(Debug)]
+/// struct Marker {
+///     x: f64,
+///     y: f64,
+/// }
+///
+/// impl Bounded<f64> for Marker {
+///     fn north_edge(&self) -> f64 {
+///         self.y
+///     }
+///     fn east_edge(&self) -> f64 {
+///         self.x
+///     }
+///     fn south_edge(&self) -> f64 {
+///         self.y
+///     }
+///     fn west_edge(&self) -> f64 {
+///         self.x
+///     }
+/// }
+///
+/// let mut qt: Quadtree<f64> = Quadtree::new(-1.0e9, 1.0e9, 2.0e9, 2.0e9);
+/// qt.insert(Rc::new(Marker { x: 123_456_789.125, y: -0.5 })).unwrap();
+/// assert_eq!(1, qt.len());
+/// ```
 #[derive(Debug)]
-pub struct Quadtree {
-    position_x: f32,
-    position_y: f32,
-    width: f32,
-    height: f32,
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(bound(
+        serialize = "T: Float + serde::Serialize",
+        deserialize = "T: Float + Debug + serde::Deserialize<'de> + 'static"
+    ))
+)]
+pub struct Quadtree<T = f32> {
+    position_x: T,
+    position_y: T,
+    width: T,
+    height: T,
+    capacity: usize,
+    depth: usize,
+    max_depth: usize,
     divided: bool,
+    #[cfg_attr(feature = "serde", serde(default))]
+    dedup: bool,
+    #[cfg_attr(feature = "serde", serde(default = "strict"))]
+    looseness: T,
     northeast_quad: Option<Rc<RefCell<Self>>>,
     northwest_quad: Option<Rc<RefCell<Self>>>,
     southeast_quad: Option<Rc<RefCell<Self>>>,
     southwest_quad: Option<Rc<RefCell<Self>>>,
-    contents: Vec<Rc<dyn Sized>>,
+    #[cfg_attr(feature = "serde", serde(with = "serialization::contents"))]
+    contents: Contents<T>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    ids: BTreeMap<ObjectId, Weak<dyn Bounded<T>>>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    next_id: u64,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    hooks: Hooks<T>,
 }
 
-/// The `Sized` trait defines four functions `north_edge()`, `east_edge()`, `south_edge()`, `west_edge()`
-/// each returning the `f32` position of the respective edge.
+/// The `Bounded` trait defines four functions `north_edge()`, `east_edge()`, `south_edge()`, `west_edge()`
+/// each returning the position of the respective edge in the coordinate type `T`, which defaults to `f32`.
 /// Implementing this trait is required to insert elements into the `Quadtree`, as it provides the boundaries
 /// for spatial partitioning.
 ///
 /// # Examples
 /// ```
+/// use spatialize::quadtree::Bounded;
+///
+/// #[derive(Debug)]
 /// struct Rectangle {
 ///     position_x: f32,
 ///     position_y: f32,
@@ -32,7 +160,7 @@ pub struct Quadtree {
 ///     height: f32,
 /// }
 ///
-/// impl Sized for Rectangle {
+/// impl Bounded for Rectangle {
 ///     fn north_edge(&self) -> f32 {
 ///         self.position_y
 ///     }
@@ -50,173 +178,4675 @@ pub struct Quadtree {
 ///     }
 /// }
 /// ```
-pub trait Sized: Debug {
-    fn north_edge(&self) -> f32;
-    fn east_edge(&self) -> f32;
-    fn south_edge(&self) -> f32;
-    fn west_edge(&self) -> f32;
+pub trait Bounded<T = f32>: Debug {
+    fn north_edge(&self) -> T;
+    fn east_edge(&self) -> T;
+    fn south_edge(&self) -> T;
+    fn west_edge(&self) -> T;
+
+    /// Returns `true` if the edges of this object and `other` overlap. Objects only touching
+    /// at an edge or a corner overlap as well, like in every query of the `Quadtree`.
+    ///
+    /// # Examples
+    /// ```
+    /// # use spatialize::quadtree::{Aabb, Bounded};
+    /// let aabb = Aabb::new(0.0, 0.0, 2.0, 2.0);
+    /// assert!(aabb.intersects(&Aabb::new(1.0, 1.0, 3.0, 3.0)));
+    /// assert!(aabb.intersects(&Aabb::new(2.0, 2.0, 3.0, 3.0)));
+    /// assert!(!aabb.intersects(&Aabb::new(2.5, 0.0, 3.0, 1.0)));
+    /// ```
+    fn intersects(&self, other: &dyn Bounded<T>) -> bool
+    where
+        T: PartialOrd,
+    {
+        !(self.north_edge() < other.south_edge()
+            || self.east_edge() < other.west_edge()
+            || self.south_edge() > other.north_edge()
+            || self.west_edge() > other.east_edge())
+    }
+
+    /// Returns `true` if the point `(x, y)` lies within the edges of this object, including
+    /// on them.
+    ///
+    /// # Examples
+    /// ```
+    /// # use spatialize::quadtree::{Aabb, Bounded};
+    /// let aabb = Aabb::new(0.0, 0.0, 2.0, 2.0);
+    /// assert!(aabb.contains_point(1.0, 2.0));
+    /// assert!(!aabb.contains_point(1.0, 2.5));
+    /// ```
+    fn contains_point(&self, x: T, y: T) -> bool
+    where
+        T: PartialOrd,
+    {
+        self.west_edge() <= x
+            && x <= self.east_edge()
+            && self.south_edge() <= y
+            && y <= self.north_edge()
+    }
+
+    /// Returns the center of the edges of this object as `(x, y)`.
+    ///
+    /// # Examples
+    /// ```
+    /// # use spatialize::quadtree::{Bounded, Point};
+    /// assert_eq!((1.0, -2.0), Point::new(1.0, -2.0).center());
+    /// ```
+    fn center(&self) -> (T, T)
+    where
+        T: Float,
+    {
+        (
+            (self.west_edge() + self.east_edge()) / two(),
+            (self.south_edge() + self.north_edge()) / two(),
+        )
+    }
+
+    /// Returns the area enclosed by the edges of this object.
+    ///
+    /// # Examples
+    /// ```
+    /// # use spatialize::quadtree::{Aabb, Bounded};
+    /// assert_eq!(6.0, Aabb::new(-1.0, 0.0, 2.0, 2.0).area());
+    /// ```
+    fn area(&self) -> T
+    where
+        T: Float,
+    {
+        (self.east_edge() - self.west_edge()) * (self.north_edge() - self.south_edge())
+    }
+}
+
+/// Deprecated alias of the `Bounded` trait, which used to be called `Sized`.
+/// It will be removed in the next release.
+pub use Bounded as Sized;
+
+/// An opaque handle to an object inserted with `Quadtree::insert_with_id`, which can be used
+/// to look up or remove the object without holding on to its `Rc`. Ids are unique within the
+/// `Quadtree` that issued them and are never reused.
+///
+/// Ids can also be created from a `u64`, like the id of an entity, to insert objects under
+/// them with `Quadtree::upsert`. Ids issued afterwards skip past every id used this way.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ObjectId(u64);
+
+impl From<u64> for ObjectId {
+    fn from(id: u64) -> Self {
+        ObjectId(id)
+    }
+}
+
+/// Whether `Quadtree::upsert` inserted a new object or updated an existing one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Upsert {
+    /// No object was stored under the id, so the object was inserted.
+    Inserted,
+    /// An object was stored under the id and was moved to match the current edges of the
+    /// object, or replaced by it.
+    Updated,
+}
+
+/// The error type returned by fallible `Quadtree` operations.
+#[derive(Debug, Clone, Copy)]
+pub enum QuadtreeError {
+    /// The object doesn't fit within the bounds of the `Quadtree`. Holds the edges of the
+    /// `object` and of the `bounds` it didn't fit in, each as `(north, east, south, west)`
+    /// converted to `f64`. The object violates every edge it reaches past, like the north edge
+    /// if `object.0 > bounds.0` or the west edge if `object.3 < bounds.3`, which tells how far
+    /// the bounds would need to grow to hold it. When comparing errors, `NaN` edges are equal
+    /// to each other, so the error type stays `Eq`.
+    OutOfBounds {
+        object: (f64, f64, f64, f64),
+        bounds: (f64, f64, f64, f64),
+    },
+    /// The object isn't stored in the `Quadtree`.
+    NotFound,
+    /// The query region doesn't overlap the bounds of the `Quadtree`. Queries treat such a
+    /// region as empty and no longer return this error.
+    NoOverlap,
+    /// The object is already stored in the `Quadtree`.
+    AlreadyPresent,
+    /// The query polygon has fewer than three points and encloses no area.
+    DegeneratePolygon,
+}
+
+impl PartialEq for QuadtreeError {
+    fn eq(&self, other: &Self) -> bool {
+        let same = |a: f64, b: f64| a == b || a.is_nan() && b.is_nan();
+        let same_edges = |a: &(f64, f64, f64, f64), b: &(f64, f64, f64, f64)| {
+            same(a.0, b.0) && same(a.1, b.1) && same(a.2, b.2) && same(a.3, b.3)
+        };
+        match (self, other) {
+            (
+                QuadtreeError::OutOfBounds { object, bounds },
+                QuadtreeError::OutOfBounds {
+                    object: other_object,
+                    bounds: other_bounds,
+                },
+            ) => same_edges(object, other_object) && same_edges(bounds, other_bounds),
+            _ => core::mem::discriminant(self) == core::mem::discriminant(other),
+        }
+    }
+}
+
+impl Eq for QuadtreeError {}
+
+impl Display for QuadtreeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            QuadtreeError::OutOfBounds { object, bounds } => write!(
+                f,
+                "Object with edges {object:?} doesn't fit within the Quadtree bounds {bounds:?}."
+            ),
+            QuadtreeError::NotFound => write!(f, "Object wasn't found in the Quadtree."),
+            QuadtreeError::NoOverlap => {
+                write!(f, "Rectangle doesn't overlap the Quadtree bounds.")
+            }
+            QuadtreeError::AlreadyPresent => {
+                write!(f, "Object is already stored in the Quadtree.")
+            }
+            QuadtreeError::DegeneratePolygon => {
+                write!(f, "Polygon has fewer than three points.")
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for QuadtreeError {}
+
+/// The relation between stored objects and the query region used by `Quadtree::get_rect_mode`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QueryMode {
+    /// Objects overlapping the query region, as returned by `get_rect`.
+    Overlap,
+    /// Objects lying entirely inside the query region.
+    Contains,
+    /// Objects entirely enclosing the query region.
+    Within,
+}
+
+/// Statistics about the shape of a `Quadtree`, as returned by `Quadtree::stats`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct TreeStats {
+    /// The number of nodes, including the root.
+    pub node_count: usize,
+    /// The number of nodes that aren't divided.
+    pub leaf_count: usize,
+    /// The maximum depth of any leaf below the root, which is at depth `0`.
+    pub max_depth: usize,
+    /// The number of stored objects.
+    pub total_objects: usize,
+}
+
+/// A private function returning the `QuadtreeError::OutOfBounds` of `sized_object` not
+/// fitting within `bounds`.
+fn out_of_bounds<T: Float>(
+    sized_object: &dyn Bounded<T>,
+    bounds: &dyn Bounded<T>,
+) -> QuadtreeError {
+    let edges = |rc: &dyn Bounded<T>| {
+        let edge = |edge: T| edge.to_f64().unwrap_or(f64::NAN);
+        (
+            edge(rc.north_edge()),
+            edge(rc.east_edge()),
+            edge(rc.south_edge()),
+            edge(rc.west_edge()),
+        )
+    };
+    QuadtreeError::OutOfBounds {
+        object: edges(sized_object),
+        bounds: edges(bounds),
+    }
+}
+
+/// A private function checking whether the edges of `inner` lie within the edges of `outer`.
+fn encloses<T: Float>(outer: &dyn Bounded<T>, inner: &dyn Bounded<T>) -> bool {
+    inner.north_edge() <= outer.north_edge()
+        && inner.east_edge() <= outer.east_edge()
+        && inner.south_edge() >= outer.south_edge()
+        && inner.west_edge() >= outer.west_edge()
+}
+
+/// A private function returning the squared distance from the point `(x, y)` to the box
+/// described by the given edges, which is zero if the point lies inside the box.
+fn distance_squared<T: Float>(north: T, east: T, south: T, west: T, x: T, y: T) -> T {
+    let dx = (west - x).max(x - east).max(T::zero());
+    let dy = (south - y).max(y - north).max(T::zero());
+    dx * dx + dy * dy
+}
+
+/// A private function returning the squared distance from the point `(x, y)` to the edges of
+/// `object`.
+fn object_distance_squared<T: Float>(object: &dyn Bounded<T>, x: T, y: T) -> T {
+    distance_squared(
+        object.north_edge(),
+        object.east_edge(),
+        object.south_edge(),
+        object.west_edge(),
+        x,
+        y,
+    )
+}
+
+/// A private function checking whether the point `(x, y)` lies within the given edges.
+fn encloses_point<T: Float>(north: T, east: T, south: T, west: T, x: T, y: T) -> bool {
+    west <= x && x <= east && south <= y && y <= north
+}
+
+/// A private function checking whether the convex polygon through `points` intersects the box
+/// described by the given edges, using the separating axis theorem. Besides the two axes of the
+/// box, the normal of every edge of the polygon is tested, in either winding order.
+fn overlaps_polygon<T: Float>(points: &[(T, T)], north: T, east: T, south: T, west: T) -> bool {
+    let project = |corners: &mut dyn Iterator<Item = (T, T)>, (nx, ny): (T, T)| {
+        corners.fold((T::infinity(), T::neg_infinity()), |(min, max), (x, y)| {
+            let projected = x * nx + y * ny;
+            (min.min(projected), max.max(projected))
+        })
+    };
+    let corners = [(west, north), (east, north), (east, south), (west, south)];
+    let edges = points
+        .iter()
+        .zip(points.iter().cycle().skip(1))
+        .map(|(&(x0, y0), &(x1, y1))| (y0 - y1, x1 - x0));
+    [(T::one(), T::zero()), (T::zero(), T::one())]
+        .into_iter()
+        .chain(edges)
+        .all(|axis| {
+            let (polygon_min, polygon_max) = project(&mut points.iter().copied(), axis);
+            let (box_min, box_max) = project(&mut corners.iter().copied(), axis);
+            !(polygon_max < box_min || box_max < polygon_min)
+        })
+}
+
+/// A private function returning the range of times `t` for which `lower <= t * delta <= upper`,
+/// used to sweep one side of a box along an axis.
+fn sweep_interval<T: Float>(lower: T, upper: T, delta: T) -> (T, T) {
+    if delta == T::zero() {
+        if lower <= T::zero() && T::zero() <= upper {
+            (T::neg_infinity(), T::infinity())
+        } else {
+            (T::infinity(), T::neg_infinity())
+        }
+    } else if delta > T::zero() {
+        (lower / delta, upper / delta)
+    } else {
+        (upper / delta, lower / delta)
+    }
+}
+
+/// A private function returning the earliest time in `[0, 1]` at which `moving` touches the box
+/// described by the given edges while it moves by `(dx, dy)`, i.e. when both overlap on both
+/// axes, or `None` if they never touch.
+fn sweep_entry<T: Float>(
+    moving: &dyn Bounded<T>,
+    dx: T,
+    dy: T,
+    north: T,
+    east: T,
+    south: T,
+    west: T,
+) -> Option<T> {
+    let (x_enter, x_exit) =
+        sweep_interval(west - moving.east_edge(), east - moving.west_edge(), dx);
+    let (y_enter, y_exit) =
+        sweep_interval(south - moving.north_edge(), north - moving.south_edge(), dy);
+    let enter = T::zero().max(x_enter).max(y_enter);
+    if enter <= T::one().min(x_exit).min(y_exit) {
+        Some(enter)
+    } else {
+        None
+    }
+}
+
+/// A pair of objects stored in a `Quadtree`, as returned by `Quadtree::overlapping_pairs`.
+pub type BoundedPair<T = f32> = (Rc<dyn Bounded<T>>, Rc<dyn Bounded<T>>);
+
+/// A shared handle to one of the quadrants of a `Quadtree`.
+type QuadrantRef<T> = Rc<RefCell<Quadtree<T>>>;
+
+/// An object waiting to be placed into a quadrant during `Quadtree::insert`.
+type Placement<T> = (QuadrantRef<T>, Entry<T>);
+
+/// A private constant holding the layer mask of objects inserted without one, which matches
+/// every masked query.
+const ALL_LAYERS: u32 = u32::MAX;
+
+/// A private constant holding the number of entries the contents of a node store inline,
+/// without a heap allocation, when the `smallvec` feature is enabled.
+#[cfg(feature = "smallvec")]
+const INLINE_CONTENTS: usize = 2;
+
+/// A private type holding the entries stored in a node. With the `smallvec` feature, nodes
+/// holding up to `INLINE_CONTENTS` objects don't allocate.
+#[cfg(not(feature = "smallvec"))]
+type Contents<T> = Vec<Entry<T>>;
+#[cfg(feature = "smallvec")]
+type Contents<T> = smallvec::SmallVec<[Entry<T>; INLINE_CONTENTS]>;
+
+/// A private type holding an object stored in a node along with its layer mask.
+#[derive(Debug, Clone)]
+struct Entry<T> {
+    reference: Reference<T>,
+    mask: u32,
+}
+
+/// A private type referring to a stored object, either by a strong reference keeping it alive
+/// or by a weak reference, in which case the entry dies along with the object.
+#[derive(Debug, Clone)]
+enum Reference<T> {
+    Strong(Rc<dyn Bounded<T>>),
+    Weak(Weak<dyn Bounded<T>>),
+}
+
+impl<T> Entry<T> {
+    /// A private function creating an entry keeping `sized_object` alive.
+    fn strong(sized_object: Rc<dyn Bounded<T>>) -> Self {
+        Entry {
+            reference: Reference::Strong(sized_object),
+            mask: ALL_LAYERS,
+        }
+    }
+
+    /// A private function creating an entry that doesn't keep `sized_object` alive.
+    fn weak(sized_object: &Rc<dyn Bounded<T>>) -> Self {
+        Entry {
+            reference: Reference::Weak(Rc::downgrade(sized_object)),
+            mask: ALL_LAYERS,
+        }
+    }
+
+    /// A private function returning the object, or `None` if it was dropped.
+    fn get(&self) -> Option<Rc<dyn Bounded<T>>> {
+        match &self.reference {
+            Reference::Strong(rc) => Some(Rc::clone(rc)),
+            Reference::Weak(weak) => weak.upgrade(),
+        }
+    }
+
+    /// A private function calling `f` with the object unless it was dropped, without cloning
+    /// strong references.
+    fn with<R>(&self, f: impl FnOnce(&Rc<dyn Bounded<T>>) -> R) -> Option<R> {
+        match &self.reference {
+            Reference::Strong(rc) => Some(f(rc)),
+            Reference::Weak(weak) => weak.upgrade().map(|rc| f(&rc)),
+        }
+    }
+
+    /// A private function checking whether the object wasn't dropped yet.
+    fn is_alive(&self) -> bool {
+        match &self.reference {
+            Reference::Strong(_) => true,
+            Reference::Weak(weak) => weak.strong_count() > 0,
+        }
+    }
+
+    /// A private function checking whether this entry refers to `sized_object`.
+    fn is(&self, sized_object: &Rc<dyn Bounded<T>>) -> bool {
+        match &self.reference {
+            Reference::Strong(rc) => Rc::ptr_eq(rc, sized_object),
+            Reference::Weak(weak) => core::ptr::addr_eq(weak.as_ptr(), Rc::as_ptr(sized_object)),
+        }
+    }
+}
+
+/// A hook set with `QuadtreeBuilder::on_subdivide`.
+type SubdivideHook<T> = Rc<RefCell<dyn FnMut(&Quadtree<T>)>>;
+
+/// A hook set with `QuadtreeBuilder::on_store`.
+type StoreHook<T> = Rc<RefCell<dyn FnMut(&Quadtree<T>, &Rc<dyn Bounded<T>>)>>;
+
+/// A private type holding the instrumentation hooks of a `Quadtree`, which every node shares.
+/// Unset hooks cost a single check where they would be called.
+struct Hooks<T> {
+    on_subdivide: Option<SubdivideHook<T>>,
+    on_store: Option<StoreHook<T>>,
+}
+
+impl<T> Default for Hooks<T> {
+    fn default() -> Self {
+        Hooks {
+            on_subdivide: None,
+            on_store: None,
+        }
+    }
+}
+
+impl<T> Clone for Hooks<T> {
+    fn clone(&self) -> Self {
+        Hooks {
+            on_subdivide: self.on_subdivide.clone(),
+            on_store: self.on_store.clone(),
+        }
+    }
+}
+
+impl<T> Debug for Hooks<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Hooks")
+            .field("on_subdivide", &self.on_subdivide.is_some())
+            .field("on_store", &self.on_store.is_some())
+            .finish()
+    }
+}
+
+/// Hooks are equal if they call the same closures.
+impl<T> PartialEq for Hooks<T> {
+    fn eq(&self, other: &Self) -> bool {
+        fn same<F: ?core::marker::Sized>(a: &Option<Rc<F>>, b: &Option<Rc<F>>) -> bool {
+            match (a, b) {
+                (Some(a), Some(b)) => Rc::ptr_eq(a, b),
+                (a, b) => a.is_none() && b.is_none(),
+            }
+        }
+        same(&self.on_subdivide, &other.on_subdivide) && same(&self.on_store, &other.on_store)
+    }
+}
+
+/// A private box given by its edges, used to query raw coordinates.
+struct Edges<T> {
+    north: T,
+    east: T,
+    south: T,
+    west: T,
+}
+
+impl<T> Debug for Edges<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Edges")
+    }
+}
+
+impl<T: Float> Bounded<T> for Edges<T> {
+    fn north_edge(&self) -> T {
+        self.north
+    }
+    fn east_edge(&self) -> T {
+        self.east
+    }
+    fn south_edge(&self) -> T {
+        self.south
+    }
+    fn west_edge(&self) -> T {
+        self.west
+    }
+}
+
+/// A private tally of the nodes visited by `Quadtree::estimate_len`: the objects counted
+/// exactly and the number of leaves below the sampled nodes.
+#[derive(Default)]
+struct LenSample {
+    objects: usize,
+    unsampled_leaves: usize,
 }
 
-impl Quadtree {
-    /// Returns a `Quadtree` with the specified boundaries.
+/// A private wrapper ordering values by a distance alone, used in the heaps of nearest
+/// neighbor searches and to sort segment hits.
+struct ByDistance<T, V>(T, V);
+
+impl<T: Float, V> PartialEq for ByDistance<T, V> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<T: Float, V> Eq for ByDistance<T, V> {}
+
+impl<T: Float, V> PartialOrd for ByDistance<T, V> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T: Float, V> Ord for ByDistance<T, V> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.partial_cmp(&other.0).unwrap_or(Ordering::Equal)
+    }
+}
+
+/// A private function returning two in the coordinate type, used to halve lengths.
+fn two<T: Float>() -> T {
+    T::one() + T::one()
+}
+
+/// A private function returning the looseness of a strict `Quadtree`, whose nodes hold only
+/// objects within their bounds.
+fn strict<T: Float>() -> T {
+    T::one()
+}
+
+/// A private function dividing the counts `part` by `whole` in the coordinate type, returning
+/// zero if `whole` is zero.
+fn ratio<T: Float>(part: usize, whole: usize) -> T {
+    match (T::from(part), T::from(whole)) {
+        (Some(part), Some(whole)) if whole > T::zero() => part / whole,
+        _ => T::zero(),
+    }
+}
+
+impl<T: Float> Quadtree<T> {
+    /// Returns a `Quadtree` with the specified boundaries and the `DEFAULT_CAPACITY`.
     ///
     /// # Examples
     ///
     /// ```
+    /// # use spatialize::quadtree::Quadtree;
     /// let position_x: f32 = -100.0;
     /// let position_y: f32 = 100.0;
     /// let width: f32 = 200.0;
     /// let height: f32 = 200.0;
     /// let qt = Quadtree::new(position_x, position_y, width, height);
     /// ```
-    pub fn new(position_x: f32, position_y: f32, width: f32, height: f32) -> Self {
+    pub fn new(position_x: T, position_y: T, width: T, height: T) -> Self {
+        Self::with_capacity(position_x, position_y, width, height, DEFAULT_CAPACITY)
+    }
+
+    /// Returns a `Quadtree` with the specified boundaries, where each node holds up to `capacity`
+    /// objects before it subdivides and redistributes its contents into the four quadrants.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use spatialize::quadtree::Quadtree;
+    /// let qt = Quadtree::with_capacity(-100.0, 100.0, 200.0, 200.0, 8);
+    /// ```
+    pub fn with_capacity(
+        position_x: T,
+        position_y: T,
+        width: T,
+        height: T,
+        capacity: usize,
+    ) -> Self {
+        Self::with_limits(
+            position_x,
+            position_y,
+            width,
+            height,
+            capacity,
+            DEFAULT_MAX_DEPTH,
+        )
+    }
+
+    /// Returns a `Quadtree` with the specified boundaries, node `capacity` and `max_depth`.
+    /// Nodes at `max_depth` stop subdividing, so objects that keep fitting into ever smaller
+    /// quadrants, like points, are stored in the deepest reachable node.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use spatialize::quadtree::{Aabb, Bounded, Quadtree};
+    /// let mut qt = Quadtree::with_limits(-10.0, 10.0, 20.0, 20.0, 0, 4);
+    /// let point: Rc<dyn Bounded> = Rc::new(Aabb::new(1.0, 1.0, 1.0, 1.0));
+    /// assert!(qt.insert(Rc::clone(&point)).is_ok());
+    /// ```
+    pub fn with_limits(
+        position_x: T,
+        position_y: T,
+        width: T,
+        height: T,
+        capacity: usize,
+        max_depth: usize,
+    ) -> Self {
         Self {
             position_x,
             position_y,
             width,
             height,
+            capacity,
+            depth: 0,
+            max_depth,
             divided: false,
+            dedup: false,
+            looseness: strict(),
             northeast_quad: None,
             northwest_quad: None,
             southeast_quad: None,
             southwest_quad: None,
-            contents: vec![],
+            contents: Contents::new(),
+            ids: BTreeMap::new(),
+            next_id: 0,
+            hooks: Hooks::default(),
         }
     }
 
-    /// A private function used to partition the `Quadtree` into four quadrants.
-    fn subdivide(&mut self) {
-        if !self.divided {
-            self.northeast_quad = Some(Rc::new(RefCell::new(Quadtree::new(
-                self.position_x + self.width / 2.0,
-                self.position_y,
-                self.width / 2.0,
-                self.height / 2.0,
-            ))));
-            self.northwest_quad = Some(Rc::new(RefCell::new(Quadtree::new(
-                self.position_x,
-                self.position_y,
-                self.width / 2.0,
-                self.height / 2.0,
-            ))));
-            self.southeast_quad = Some(Rc::new(RefCell::new(Quadtree::new(
-                self.position_x + self.width / 2.0,
-                self.position_y - self.height / 2.0,
-                self.width / 2.0,
-                self.height / 2.0,
-            ))));
-            self.southwest_quad = Some(Rc::new(RefCell::new(Quadtree::new(
-                self.position_x,
-                self.position_y - self.height / 2.0,
-                self.width / 2.0,
-                self.height / 2.0,
-            ))));
-            self.divided = true;
-        }
+    /// Returns a `QuadtreeBuilder` for configuring a `Quadtree` before creating it.
+    ///
+    /// # Examples
+    /// ```
+    /// # use spatialize::quadtree::Quadtree;
+    /// let qt: Quadtree = Quadtree::builder()
+    ///     .bounds(-100.0, 100.0, 200.0, 200.0)
+    ///     .capacity(8)
+    ///     .build();
+    /// assert_eq!((-100.0, 100.0, 200.0, 200.0), qt.bounds());
+    /// ```
+    pub fn builder() -> QuadtreeBuilder<T> {
+        QuadtreeBuilder::new()
     }
 
-    /// Inserts an object implementing the `Sized` trait.
+    /// Returns a `Quadtree` with the specified boundaries containing every object of `objects`.
+    /// If an object doesn't fit within the bounds, building stops and that object is returned
+    /// as the error.
     ///
     /// # Examples
     /// ```
-    /// let mut qt = Quadtree::new(-10.0, 10.0, 20.0, 20.0);
-    /// let sized_object: Rc<dyn Sized> = Rc::new(Rectangle::new(0.0, 0.0, 5.0, 5.0));
-    /// match qt.insert(Rc::clone(&sized_object)) {
-    ///     Ok(_) => (),
-    ///     Err(e) => eprintln!("{}", e),
-    /// }
+    /// # use std::rc::Rc;
+    /// # use spatialize::quadtree::{Aabb, Bounded, Quadtree};
+    /// let objects: Vec<Rc<dyn Bounded>> = vec![
+    ///     Rc::new(Aabb::new(0.0, -5.0, 5.0, 0.0)),
+    ///     Rc::new(Aabb::new(-8.0, 6.0, -6.0, 8.0)),
+    /// ];
+    /// let qt = Quadtree::from_bounds_and_iter(-10.0, 10.0, 20.0, 20.0, objects).unwrap();
+    /// assert_eq!(2, qt.len());
+    ///
+    /// let outside: Rc<dyn Bounded> = Rc::new(Aabb::new(20.0, 19.0, 21.0, 20.0));
+    /// let result = Quadtree::from_bounds_and_iter(-10.0, 10.0, 20.0, 20.0, [Rc::clone(&outside)]);
+    /// assert!(Rc::ptr_eq(&outside, &result.unwrap_err()));
     /// ```
-    pub fn insert(&mut self, sized_object: Rc<dyn Sized>) -> Result<(), String> {
-        if sized_object.north_edge() <= self.position_y
-            && sized_object.east_edge() <= self.position_x + self.width
-            && sized_object.south_edge() >= self.position_y - self.height
-            && sized_object.west_edge() >= self.position_x
-        {
-            //Object fits in Quadtree
-            if !self.divided {
-                self.subdivide();
-            }
-            if let Some(rc_ref) = &self.northeast_quad {
-                if let Ok(_) = rc_ref.borrow_mut().insert(Rc::clone(&sized_object)) {
-                    return Ok(());
-                }
-            }
-            if let Some(rc_ref) = &self.northwest_quad {
-                if let Ok(_) = rc_ref.borrow_mut().insert(Rc::clone(&sized_object)) {
-                    return Ok(());
-                }
-            }
-            if let Some(rc_ref) = &self.southeast_quad {
-                if let Ok(_) = rc_ref.borrow_mut().insert(Rc::clone(&sized_object)) {
-                    return Ok(());
-                }
-            }
-            if let Some(rc_ref) = &self.southwest_quad {
-                if let Ok(_) = rc_ref.borrow_mut().insert(Rc::clone(&sized_object)) {
-                    return Ok(());
-                }
+    pub fn from_bounds_and_iter<I: IntoIterator<Item = Rc<dyn Bounded<T>>>>(
+        position_x: T,
+        position_y: T,
+        width: T,
+        height: T,
+        objects: I,
+    ) -> Result<Self, Rc<dyn Bounded<T>>> {
+        let mut quadtree = Self::new(position_x, position_y, width, height);
+        for sized_object in objects {
+            if quadtree.insert(Rc::clone(&sized_object)).is_err() {
+                return Err(sized_object);
             }
-
-            //Object doesn't fit in any divisions
-            self.contents.push(sized_object);
-            Ok(())
-        } else {
-            Err(String::from(
-                "Object doesn't fit within the Quadtree bounds.",
-            ))
         }
+        Ok(quadtree)
     }
 
-    /// Searches the `Quadtree` using a two-dimensional view that implementing `Sized`
+    /// Returns a `Quadtree` with the given `(position_x, position_y, width, height)` bounds,
+    /// node `capacity` and `max_depth`, containing all of `objects`.
+    ///
+    /// Instead of inserting the objects one at a time, they are partitioned among the quadrants
+    /// top-down and every node is built once, only subdividing nodes holding more objects than
+    /// the capacity. The resulting tree is the same as when inserting the objects one by one.
+    /// If an object doesn't fit within the bounds, that object is returned as the error.
     ///
     /// # Examples
     /// ```
-    /// let mut qt = Quadtree::new(-10.0, 10.0, 20.0, 20.0);
-    /// let sized_object: Rc<dyn Sized> = Rc::new(Rectangle::new(0.0, 0.0, 5.0, 5.0));
-    /// match qt.insert(Rc::clone(&sized_object)) {
-    ///     Ok(_) => {
-    ///         let rect_view: Rc<dyn Sized> = Rc::new(Rectangle::new(-2, 2, 10.0, 10.0));
-    ///         let mut result_vec: Vec<Rc<dyn Sized>> = vec![];
-    ///         match qt.get_rect(rect_view, &mut result_vec) {
-    ///             Ok(_) => assert_eq!(1, result_vec.len()),
-    ///             Err(e) => eprintln!("{}", e),
-    ///         }
-    ///     },
-    ///     Err(e) => eprintln!("{}", e),
+    /// # use std::rc::Rc;
+    /// # use spatialize::quadtree::{Aabb, Bounded, Quadtree};
+    /// let objects: Vec<Rc<dyn Bounded>> = (0..100)
+    ///     .map(|i| {
+    ///         let x = (i % 10) as f32 * 2.0 - 10.0;
+    ///         let y = (i / 10) as f32 * 2.0 - 8.0;
+    ///         Rc::new(Aabb::new(x, y - 1.5, x + 1.5, y)) as Rc<dyn Bounded>
+    ///     })
+    ///     .collect();
+    ///
+    /// let built = Quadtree::build((-10.0, 10.0, 20.0, 20.0), &objects, 4, 8).unwrap();
+    /// let mut inserted = Quadtree::with_limits(-10.0, 10.0, 20.0, 20.0, 4, 8);
+    /// for sized_object in objects.iter() {
+    ///     inserted.insert(Rc::clone(sized_object)).unwrap();
     /// }
+    /// assert_eq!(inserted.stats(), built.stats());
+    ///
+    /// let query = |qt: &Quadtree| {
+    ///     let rect_view = Aabb::new(-3.0, -2.0, 4.0, 3.0);
+    ///     let mut result_vec: Vec<Rc<dyn Bounded>> = vec![];
+    ///     qt.get_rect(&rect_view, &mut result_vec).unwrap();
+    ///     result_vec
+    /// };
+    /// let (built_result, inserted_result) = (query(&built), query(&inserted));
+    /// assert_eq!(inserted_result.len(), built_result.len());
+    /// assert!(built_result
+    ///     .iter()
+    ///     .all(|rc| inserted_result.iter().any(|other| Rc::ptr_eq(rc, other))));
     /// ```
-    pub fn get_rect(
-        &self,
-        rect: Rc<dyn Sized>,
-        vec: &mut Vec<Rc<dyn Sized>>,
-    ) -> Result<(), String> {
-        if !(rect.north_edge() < self.position_y - self.height
-            || rect.east_edge() < self.position_x
-            || rect.south_edge() > self.position_y
-            || rect.west_edge() > self.position_x + self.width)
-        {
-            if self.divided {
-                if let Some(rc_ref) = &self.northeast_quad {
-                    let _ = rc_ref.borrow().get_rect(Rc::clone(&rect), vec);
-                }
-                if let Some(rc_ref) = &self.northwest_quad {
-                    let _ = rc_ref.borrow().get_rect(Rc::clone(&rect), vec);
-                }
-                if let Some(rc_ref) = &self.southeast_quad {
-                    let _ = rc_ref.borrow().get_rect(Rc::clone(&rect), vec);
-                }
-                if let Some(rc_ref) = &self.southwest_quad {
-                    let _ = rc_ref.borrow().get_rect(Rc::clone(&rect), vec);
-                }
-            }
-            for rc in self.contents.iter() {
-                vec.push(Rc::clone(&rc));
-            }
-            Ok(())
-        } else {
-            Err(String::from(
-                "Rectangle doesn't overlap the Quadtree bounds.",
-            ))
+    pub fn build(
+        bounds: (T, T, T, T),
+        objects: &[Rc<dyn Bounded<T>>],
+        capacity: usize,
+        max_depth: usize,
+    ) -> Result<Self, Rc<dyn Bounded<T>>> {
+        let (position_x, position_y, width, height) = bounds;
+        let mut quadtree =
+            Self::with_limits(position_x, position_y, width, height, capacity, max_depth);
+        if let Some(sized_object) = objects.iter().find(|rc| !quadtree.fits(&***rc)) {
+            return Err(Rc::clone(sized_object));
+        }
+        quadtree.build_from(objects.to_vec());
+        Ok(quadtree)
+    }
+
+    /// Returns a `Quadtree` with node `capacity` and `max_depth` containing all of `objects`,
+    /// whose root bounds are the smallest box enclosing every object grown by `padding` on each
+    /// side, so no object is rejected for lying outside. Without objects, the root spans
+    /// `padding` in every direction from the origin.
+    ///
+    /// # Examples
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use spatialize::quadtree::{Aabb, Bounded, Quadtree};
+    /// let objects: Vec<Rc<dyn Bounded>> = vec![
+    ///     Rc::new(Aabb::new(-40.0, 20.0, -35.0, 25.0)),
+    ///     Rc::new(Aabb::new(12.0, -5.0, 20.0, -3.0)),
+    ///     Rc::new(Aabb::new(30.0, -16.0, 32.0, -10.0)),
+    /// ];
+    /// let qt = Quadtree::with_root_from_extent(&objects, 1.0, 1, 8);
+    /// assert_eq!((-41.0, 26.0, 74.0, 43.0), qt.bounds());
+    /// assert_eq!(3, qt.len());
+    ///
+    /// let (position_x, position_y, width, height) = qt.bounds();
+    /// assert!(objects.iter().all(|rc| {
+    ///     rc.west_edge() >= position_x
+    ///         && rc.east_edge() <= position_x + width
+    ///         && rc.north_edge() <= position_y
+    ///         && rc.south_edge() >= position_y - height
+    /// }));
+    /// ```
+    pub fn with_root_from_extent(
+        objects: &[Rc<dyn Bounded<T>>],
+        padding: T,
+        capacity: usize,
+        max_depth: usize,
+    ) -> Self {
+        let (north, east, south, west) = objects
+            .iter()
+            .map(|rc| {
+                (
+                    rc.north_edge(),
+                    rc.east_edge(),
+                    rc.south_edge(),
+                    rc.west_edge(),
+                )
+            })
+            .reduce(|(north, east, south, west), edges| {
+                (
+                    north.max(edges.0),
+                    east.max(edges.1),
+                    south.min(edges.2),
+                    west.min(edges.3),
+                )
+            })
+            .unwrap_or((T::zero(), T::zero(), T::zero(), T::zero()));
+        let mut quadtree = Self::with_limits(
+            west - padding,
+            north + padding,
+            east - west + two::<T>() * padding,
+            north - south + two::<T>() * padding,
+            capacity,
+            max_depth,
+        );
+        for sized_object in objects {
+            //Every object lies within the root, clamping only guards against rounding the
+            //east and south edges
+            let _ = quadtree.insert_clamped(Rc::clone(sized_object));
+        }
+        quadtree
+    }
+
+    /// A private function filling this empty node with `objects`, all of which fit within it,
+    /// partitioning them among new quadrants if they exceed the capacity.
+    fn build_from(&mut self, objects: Vec<Rc<dyn Bounded<T>>>) {
+        if objects.len() <= self.capacity || self.depth >= self.max_depth {
+            self.contents = objects.into_iter().map(Entry::strong).collect();
+            return;
+        }
+        self.subdivide();
+        let quadrants: Vec<_> = self.placement_order().cloned().collect();
+        let mut partitions: Vec<Vec<Rc<dyn Bounded<T>>>> = vec![vec![]; quadrants.len()];
+        for sized_object in objects {
+            let quadrant = self.classify(&*sized_object);
+            match quadrant.and_then(|quadrant| {
+                quadrants
+                    .iter()
+                    .position(|rc_ref| Rc::ptr_eq(rc_ref, quadrant))
+            }) {
+                Some(index) => partitions[index].push(sized_object),
+                None => self.contents.push(Entry::strong(sized_object)),
+            }
+        }
+        for (rc_ref, partition) in quadrants.iter().zip(partitions) {
+            rc_ref.borrow_mut().build_from(partition);
+        }
+    }
+
+    /// Returns a `Quadtree` with the given `(position_x, position_y, width, height)` bounds whose
+    /// quadrant matching the bounds of `existing` is `existing` itself, leaving the other three
+    /// quadrants empty. The new root takes over the capacity and limits of `existing`, and one
+    /// more level of depth so `existing` can keep subdividing as before.
+    ///
+    /// If the bounds of `existing` aren't exactly one quadrant of the given bounds, `existing`
+    /// is returned unchanged and boxed as the error.
+    ///
+    /// # Examples
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use spatialize::quadtree::{Aabb, Bounded, Quadtree};
+    /// let mut local = Quadtree::new(0.0, 0.0, 10.0, 10.0);
+    /// local.insert(Rc::new(Aabb::new(2.0, -3.0, 3.0, -2.0))).unwrap();
+    ///
+    /// let mut world = Quadtree::from_existing_root((-10.0, 10.0, 20.0, 20.0), local).unwrap();
+    /// assert_eq!(1, world.southeast().unwrap().borrow().len());
+    /// world.insert(Rc::new(Aabb::new(-8.0, 7.0, -7.0, 8.0))).unwrap();
+    ///
+    /// let rect_view = Aabb::new(-10.0, -10.0, 10.0, 10.0);
+    /// let mut result_vec: Vec<Rc<dyn Bounded>> = vec![];
+    /// world.get_rect(&rect_view, &mut result_vec).unwrap();
+    /// assert_eq!(2, result_vec.len());
+    ///
+    /// let misaligned = Quadtree::new(1.0, 0.0, 10.0, 10.0);
+    /// let result = Quadtree::from_existing_root((-10.0, 10.0, 20.0, 20.0), misaligned);
+    /// assert_eq!((1.0, 0.0, 10.0, 10.0), result.unwrap_err().bounds());
+    /// ```
+    pub fn from_existing_root(bounds: (T, T, T, T), mut existing: Self) -> Result<Self, Box<Self>> {
+        let (position_x, position_y, width, height) = bounds;
+        let mut root = Self::with_limits(
+            position_x,
+            position_y,
+            width,
+            height,
+            existing.capacity,
+            existing.max_depth + 1,
+        );
+        root.depth = existing.depth;
+        root.dedup = existing.dedup;
+        root.looseness = existing.looseness;
+        root.hooks = existing.hooks.clone();
+        root.subdivide();
+
+        let slot = [
+            &mut root.northeast_quad,
+            &mut root.northwest_quad,
+            &mut root.southeast_quad,
+            &mut root.southwest_quad,
+        ]
+        .into_iter()
+        .find(|quadrant| {
+            quadrant
+                .as_ref()
+                .is_some_and(|rc_ref| rc_ref.borrow().bounds() == existing.bounds())
+        });
+        let Some(slot) = slot else {
+            return Err(Box::new(existing));
+        };
+        root.ids = core::mem::take(&mut existing.ids);
+        root.next_id = existing.next_id;
+        existing.deepen();
+        *slot = Some(Rc::new(RefCell::new(existing)));
+        Ok(root)
+    }
+
+    /// Returns a `Quadtree` with the given bounds, as `(position_x, position_y, width, height)`,
+    /// that is subdivided down to `depth` up front, forming a uniform grid of 4^`depth` leaves.
+    ///
+    /// Inserting an object only routes it down to the smallest cell it fits in, without ever
+    /// deciding whether to subdivide, which makes sense for evenly spread objects. Nodes have a
+    /// capacity of `0` and a maximum depth of `depth`, so cells merged by `prune` or `clear`
+    /// subdivide down to the grid again as soon as they receive an object. The grid trades the
+    /// memory of its nodes, including empty ones, for predictable performance.
+    ///
+    /// # Examples
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use spatialize::quadtree::{Aabb, Bounded, Quadtree};
+    /// let mut qt = Quadtree::grid((-10.0, 10.0, 20.0, 20.0), 2);
+    /// assert_eq!(16, qt.stats().leaf_count);
+    /// assert_eq!(2, qt.depth());
+    ///
+    /// qt.insert(Rc::new(Aabb::new(-9.0, 8.0, -8.0, 9.0))).unwrap();
+    /// qt.insert(Rc::new(Aabb::new(1.0, -9.0, 9.0, -1.0))).unwrap();
+    /// assert_eq!(16, qt.stats().leaf_count);
+    /// assert_eq!(0, qt.node_len());
+    /// ```
+    pub fn grid(bounds: (T, T, T, T), depth: usize) -> Self {
+        let (position_x, position_y, width, height) = bounds;
+        let mut grid = Self::with_limits(position_x, position_y, width, height, 0, depth);
+        grid.subdivide_to(depth);
+        grid
+    }
+
+    /// A private function subdividing this node and its quadrants down to `depth`.
+    fn subdivide_to(&mut self, depth: usize) {
+        if self.depth < depth {
+            self.subdivide();
+            for rc_ref in self.quadrants() {
+                rc_ref.borrow_mut().subdivide_to(depth);
+            }
+        }
+    }
+
+    /// Returns the bounds of this node as `(position_x, position_y, width, height)`.
+    ///
+    /// # Examples
+    /// ```
+    /// # use spatialize::quadtree::Quadtree;
+    /// let qt: Quadtree = Quadtree::new(-10.0, 10.0, 20.0, 20.0);
+    /// assert_eq!((-10.0, 10.0, 20.0, 20.0), qt.bounds());
+    /// ```
+    pub fn bounds(&self) -> (T, T, T, T) {
+        (self.position_x, self.position_y, self.width, self.height)
+    }
+
+    /// Returns the smallest box enclosing the edges of every stored object as
+    /// `(position_x, position_y, width, height)` like `bounds`, or `None` if the `Quadtree` is
+    /// empty. Comparing it to `bounds` shows when objects drift towards the edges of the root.
+    ///
+    /// # Examples
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use spatialize::quadtree::{Aabb, Bounded, Quadtree};
+    /// let mut qt = Quadtree::new(-10.0, 10.0, 20.0, 20.0);
+    /// assert_eq!(None, qt.extent());
+    /// qt.insert(Rc::new(Aabb::new(0.0, -5.0, 5.0, 0.0))).unwrap();
+    /// qt.insert(Rc::new(Aabb::new(-8.0, 6.0, -6.0, 8.0))).unwrap();
+    /// assert_eq!(Some((-8.0, 8.0, 13.0, 13.0)), qt.extent());
+    /// ```
+    pub fn extent(&self) -> Option<(T, T, T, T)> {
+        let (north, east, south, west) = self.iter().fold(None, |edges, rc| {
+            let (north, east, south, west) = edges.unwrap_or((
+                T::neg_infinity(),
+                T::neg_infinity(),
+                T::infinity(),
+                T::infinity(),
+            ));
+            Some((
+                north.max(rc.north_edge()),
+                east.max(rc.east_edge()),
+                south.min(rc.south_edge()),
+                west.min(rc.west_edge()),
+            ))
+        })?;
+        Some((west, north, east - west, north - south))
+    }
+
+    /// Returns `true` if this node is partitioned into four quadrants.
+    ///
+    /// # Examples
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use spatialize::quadtree::{Aabb, Bounded, Quadtree};
+    /// let mut qt = Quadtree::new(-10.0, 10.0, 20.0, 20.0);
+    /// assert!(!qt.is_divided());
+    /// qt.insert(Rc::new(Aabb::new(0.0, -5.0, 5.0, 0.0))).unwrap();
+    /// assert!(qt.is_divided());
+    ///
+    /// let northeast = qt.northeast().unwrap();
+    /// assert_eq!((0.0, 10.0, 10.0, 10.0), northeast.borrow().bounds());
+    /// ```
+    pub fn is_divided(&self) -> bool {
+        self.divided
+    }
+
+    /// Returns the northeast quadrant, or `None` if the node isn't divided.
+    ///
+    /// # Examples
+    /// ```
+    /// # use spatialize::quadtree::Quadtree;
+    /// let qt: Quadtree = Quadtree::new(-10.0, 10.0, 20.0, 20.0);
+    /// assert!(qt.northeast().is_none());
+    /// ```
+    pub fn northeast(&self) -> Option<Rc<RefCell<Self>>> {
+        self.northeast_quad.clone()
+    }
+
+    /// Returns the northwest quadrant, or `None` if the node isn't divided.
+    ///
+    /// # Examples
+    /// ```
+    /// # use spatialize::quadtree::Quadtree;
+    /// let qt: Quadtree = Quadtree::new(-10.0, 10.0, 20.0, 20.0);
+    /// assert!(qt.northwest().is_none());
+    /// ```
+    pub fn northwest(&self) -> Option<Rc<RefCell<Self>>> {
+        self.northwest_quad.clone()
+    }
+
+    /// Returns the southeast quadrant, or `None` if the node isn't divided.
+    ///
+    /// # Examples
+    /// ```
+    /// # use spatialize::quadtree::Quadtree;
+    /// let qt: Quadtree = Quadtree::new(-10.0, 10.0, 20.0, 20.0);
+    /// assert!(qt.southeast().is_none());
+    /// ```
+    pub fn southeast(&self) -> Option<Rc<RefCell<Self>>> {
+        self.southeast_quad.clone()
+    }
+
+    /// Returns the southwest quadrant, or `None` if the node isn't divided.
+    ///
+    /// # Examples
+    /// ```
+    /// # use spatialize::quadtree::Quadtree;
+    /// let qt: Quadtree = Quadtree::new(-10.0, 10.0, 20.0, 20.0);
+    /// assert!(qt.southwest().is_none());
+    /// ```
+    pub fn southwest(&self) -> Option<Rc<RefCell<Self>>> {
+        self.southwest_quad.clone()
+    }
+
+    /// Returns the deepest quadrant containing the point `(x, y)`, or `None` if the point lies
+    /// outside the `Quadtree` or the `Quadtree` isn't divided, as the root node itself isn't
+    /// shared behind an `Rc`.
+    ///
+    /// A point exactly on the border between quadrants belongs to each of them; the walk
+    /// follows the lower and then the left one, matching where `insert` places objects on a
+    /// boundary.
+    ///
+    /// # Examples
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use spatialize::quadtree::{Aabb, Bounded, Quadtree};
+    /// let mut qt = Quadtree::with_limits(-10.0, 10.0, 20.0, 20.0, 0, 2);
+    /// assert!(qt.locate(1.0, 1.0).is_none());
+    /// qt.insert(Rc::new(Aabb::new(1.0, 1.0, 1.0, 1.0))).unwrap();
+    ///
+    /// let leaf = qt.locate(1.0, 1.0).unwrap();
+    /// assert_eq!((0.0, 5.0, 5.0, 5.0), leaf.borrow().bounds());
+    /// // on the center of the root, the southwest quadrant is followed
+    /// let leaf = qt.locate(0.0, 0.0).unwrap();
+    /// assert_eq!((-10.0, 0.0, 10.0, 10.0), leaf.borrow().bounds());
+    /// assert!(qt.locate(20.0, 0.0).is_none());
+    /// ```
+    pub fn locate(&self, x: T, y: T) -> Option<Rc<RefCell<Self>>> {
+        if !self.contains_point(x, y) {
+            return None;
+        }
+        let mut node = Rc::clone(
+            self.placement_order()
+                .find(|q| q.borrow().contains_point(x, y))?,
+        );
+        loop {
+            let next = node
+                .borrow()
+                .placement_order()
+                .find(|q| q.borrow().contains_point(x, y))
+                .cloned();
+            match next {
+                Some(quadrant) => node = quadrant,
+                None => return Some(node),
+            }
+        }
+    }
+
+    /// A private function returning the existing quadrants in the order southwest, southeast,
+    /// northwest, northeast, so the first one containing something on the boundary between
+    /// quadrants is the lower and then the left one, matching `classify`.
+    fn placement_order(&self) -> impl Iterator<Item = &Rc<RefCell<Self>>> {
+        self.quadrants().rev()
+    }
+
+    /// A private function returning the existing quadrants in the order
+    /// northeast, northwest, southeast, southwest.
+    fn quadrants(&self) -> impl DoubleEndedIterator<Item = &Rc<RefCell<Self>>> {
+        [
+            &self.northeast_quad,
+            &self.northwest_quad,
+            &self.southeast_quad,
+            &self.southwest_quad,
+        ]
+        .into_iter()
+        .flatten()
+    }
+
+    /// A private function returning the edges of the region objects stored below this node
+    /// lie in. In a loose `Quadtree`, the bounds of every node but the root are expanded
+    /// around their center by the looseness.
+    fn loose_edges(&self) -> Edges<T> {
+        let (margin_x, margin_y) = if self.depth == 0 {
+            (T::zero(), T::zero())
+        } else {
+            let expansion = (self.looseness - T::one()) / two();
+            (self.width * expansion, self.height * expansion)
+        };
+        Edges {
+            north: self.position_y + margin_y,
+            east: self.position_x + self.width + margin_x,
+            south: self.position_y - self.height - margin_y,
+            west: self.position_x - margin_x,
+        }
+    }
+
+    /// A private function returning the squared distance from `(x, y)` to the loose bounds of
+    /// this node.
+    fn distance_squared(&self, x: T, y: T) -> T {
+        object_distance_squared(&self.loose_edges(), x, y)
+    }
+
+    /// A private function checking whether the point `(x, y)` lies within the bounds of this
+    /// node, not counting the expansion of a loose `Quadtree`.
+    fn contains_point(&self, x: T, y: T) -> bool {
+        encloses_point(
+            self.position_y,
+            self.position_x + self.width,
+            self.position_y - self.height,
+            self.position_x,
+            x,
+            y,
+        )
+    }
+
+    /// A private function checking whether `rect` overlaps the loose bounds of this node.
+    fn intersects(&self, rect: &dyn Bounded<T>) -> bool {
+        rect.intersects(&self.loose_edges())
+    }
+
+    /// A private function checking whether the loose bounds of this node and `other` overlap.
+    fn intersects_node(&self, other: &Self) -> bool {
+        self.loose_edges().intersects(&other.loose_edges())
+    }
+
+    /// A private function checking whether `sized_object` lies within the loose bounds of this
+    /// node.
+    fn fits(&self, sized_object: &dyn Bounded<T>) -> bool {
+        encloses(&self.loose_edges(), sized_object)
+    }
+
+    /// A private function creating a quadrant of half this node's size at the given position,
+    /// one level deeper and with the same capacity and maximum depth.
+    fn quadrant(&self, position_x: T, position_y: T) -> Rc<RefCell<Self>> {
+        let mut quadrant = Quadtree::with_limits(
+            position_x,
+            position_y,
+            self.width / two(),
+            self.height / two(),
+            self.capacity,
+            self.max_depth,
+        );
+        quadrant.depth = self.depth + 1;
+        quadrant.dedup = self.dedup;
+        quadrant.looseness = self.looseness;
+        quadrant.hooks = self.hooks.clone();
+        Rc::new(RefCell::new(quadrant))
+    }
+
+    /// A private function used to partition the `Quadtree` into four quadrants. Objects
+    /// already stored in this node are left for the caller to redistribute.
+    fn subdivide(&mut self) {
+        if !self.divided {
+            let half_width = self.width / two();
+            let half_height = self.height / two();
+            self.northeast_quad =
+                Some(self.quadrant(self.position_x + half_width, self.position_y));
+            self.northwest_quad = Some(self.quadrant(self.position_x, self.position_y));
+            self.southeast_quad =
+                Some(self.quadrant(self.position_x + half_width, self.position_y - half_height));
+            self.southwest_quad =
+                Some(self.quadrant(self.position_x, self.position_y - half_height));
+            self.divided = true;
+            if let Some(hook) = &self.hooks.on_subdivide {
+                (hook.borrow_mut())(self);
+            }
+        }
+    }
+
+    /// A private function placing `entry`, whose object fits in this node, one level down.
+    /// Objects that have to move into a quadrant are queued in `pending` together with that
+    /// quadrant instead of being inserted right away, so inserting never recurses.
+    fn place(&mut self, entry: Entry<T>, pending: &mut VecDeque<Placement<T>>) {
+        if self.divided {
+            self.place_in_quadrants(entry, pending);
+        } else {
+            self.contents.push(entry);
+            if self.contents.len() > self.capacity && self.depth < self.max_depth {
+                self.subdivide();
+                // drain rather than take the contents, keeping any capacity reserved for them
+                let contents: Vec<_> = self.contents.drain(..).collect();
+                for entry in contents {
+                    self.place_in_quadrants(entry, pending);
+                }
+            } else {
+                self.stored();
+            }
+        }
+    }
+
+    /// A private function calling the `on_store` hook, if set, with this node and the object
+    /// last pushed into its contents.
+    fn stored(&self) {
+        if let Some(hook) = &self.hooks.on_store {
+            if let Some(sized_object) = self.contents.last().and_then(Entry::get) {
+                (hook.borrow_mut())(self, &sized_object);
+            }
+        }
+    }
+
+    /// A private function returning the quadrant `sized_object`, which fits in this node, is
+    /// placed in, or `None` if it straddles the center of the node.
+    ///
+    /// The quadrant is chosen by comparing the edges of the object with the center instead of
+    /// trying each quadrant in turn. An object touching the center lines from the west or the
+    /// south, or lying on them, belongs to the quadrant to the west or south, like with
+    /// `placement_order`. In a loose `Quadtree`, an object straddling the center goes to the
+    /// quadrant containing its own center instead, as long as it fits in its loose bounds.
+    fn classify(&self, sized_object: &dyn Bounded<T>) -> Option<&Rc<RefCell<Self>>> {
+        let center_x = self.position_x + self.width / two();
+        let center_y = self.position_y - self.height / two();
+        let loose = self.looseness > strict();
+        let (object_x, object_y) = sized_object.center();
+        let west = if sized_object.east_edge() <= center_x {
+            true
+        } else if sized_object.west_edge() >= center_x {
+            false
+        } else if loose {
+            object_x <= center_x
+        } else {
+            return None;
+        };
+        let south = if sized_object.north_edge() <= center_y {
+            true
+        } else if sized_object.south_edge() >= center_y {
+            false
+        } else if loose {
+            object_y <= center_y
+        } else {
+            return None;
+        };
+        let quadrant = match (west, south) {
+            (false, false) => &self.northeast_quad,
+            (true, false) => &self.northwest_quad,
+            (false, true) => &self.southeast_quad,
+            (true, true) => &self.southwest_quad,
+        };
+        // guards against rounding in the bounds of the quadrant, keeping the object in this node
+        quadrant
+            .as_ref()
+            .filter(|rc_ref| rc_ref.borrow().fits(sized_object))
+    }
+
+    /// A private function queueing `entry` for the quadrant its object is classified into,
+    /// keeping it in this node if it straddles the center. Entries of dropped objects are
+    /// discarded.
+    fn place_in_quadrants(&mut self, entry: Entry<T>, pending: &mut VecDeque<Placement<T>>) {
+        let Some(sized_object) = entry.get() else {
+            return;
+        };
+        let quadrant = self.classify(&*sized_object).cloned();
+        match quadrant {
+            Some(rc_ref) => pending.push_back((rc_ref, entry)),
+            //Object doesn't fit in any divisions
+            None => {
+                self.contents.push(entry);
+                self.stored();
+            }
+        }
+    }
+
+    /// Inserts an object implementing the `Bounded` trait.
+    ///
+    /// Objects accumulate in a node until its capacity is exceeded, at which point the node
+    /// subdivides and moves its objects into the quadrants they fit in. Nodes at the maximum
+    /// depth never subdivide.
+    ///
+    /// The quadrant of an object is found by comparing its edges with the center of the node.
+    /// Objects straddling the center stay in the node. Objects touching or lying on the center
+    /// lines go to the quadrant to the west and then to the south of them.
+    ///
+    /// A `Quadtree` built with `QuadtreeBuilder::dedup` rejects objects it already stores, see
+    /// `insert_unique`.
+    ///
+    /// Inserting walks down the quadrants in a loop rather than by recursion, so deep trees
+    /// are limited by `max_depth` and memory, not by the size of the call stack.
+    ///
+    /// # Examples
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use spatialize::quadtree::{Aabb, Bounded, Quadtree};
+    /// let mut qt = Quadtree::new(-10.0, 10.0, 20.0, 20.0);
+    /// let sized_object: Rc<dyn Bounded> = Rc::new(Aabb::new(0.0, -5.0, 5.0, 0.0));
+    /// match qt.insert(Rc::clone(&sized_object)) {
+    ///     Ok(_) => (),
+    ///     Err(e) => eprintln!("{}", e),
+    /// }
+    /// ```
+    ///
+    /// Objects are placed by their position relative to the center:
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use spatialize::quadtree::{Aabb, Bounded, Point, Quadtree};
+    /// let mut qt = Quadtree::with_limits(-10.0, 10.0, 20.0, 20.0, 0, 1);
+    /// qt.insert(Rc::new(Aabb::new(2.0, 7.0, 3.0, 8.0))).unwrap();
+    /// qt.insert(Rc::new(Aabb::new(-8.0, -3.0, -7.0, -2.0))).unwrap();
+    /// // touching the center lines from the west and the south
+    /// qt.insert(Rc::new(Aabb::new(-1.0, -2.0, 0.0, -1.0))).unwrap();
+    /// // on the center, which belongs to the southwest quadrant
+    /// qt.insert(Rc::new(Point::new(0.0, 0.0))).unwrap();
+    /// // straddling the vertical center line
+    /// qt.insert(Rc::new(Aabb::new(-1.0, 4.0, 1.0, 5.0))).unwrap();
+    ///
+    /// assert_eq!(1, qt.northeast().unwrap().borrow().len());
+    /// assert_eq!(0, qt.northwest().unwrap().borrow().len());
+    /// assert_eq!(0, qt.southeast().unwrap().borrow().len());
+    /// assert_eq!(3, qt.southwest().unwrap().borrow().len());
+    /// assert_eq!(1, qt.node_len());
+    /// ```
+    ///
+    /// Clustered objects in a very deep tree don't overflow the stack:
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use spatialize::quadtree::{Bounded, Point, Quadtree};
+    /// let mut qt: Quadtree<f64> = Quadtree::with_limits(-1.0, 1.0, 2.0, 2.0, 1, 1000);
+    /// let mut seed: u64 = 42;
+    /// for _ in 0..500 {
+    ///     seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+    ///     let jitter = (seed >> 11) as f64 / (1u64 << 53) as f64 * 1.0e-12;
+    ///     qt.insert(Rc::new(Point::new(0.25 + jitter, 0.25 - jitter))).unwrap();
+    /// }
+    /// assert_eq!(500, qt.len());
+    ///
+    /// let mut hits: Vec<Rc<dyn Bounded<f64>>> = vec![];
+    /// qt.query_circle(0.25, 0.25, 1.0e-9, &mut hits);
+    /// assert_eq!(500, hits.len());
+    /// ```
+    pub fn insert(&mut self, sized_object: Rc<dyn Bounded<T>>) -> Result<(), QuadtreeError> {
+        self.insert_entry(&sized_object, Entry::strong(Rc::clone(&sized_object)))
+    }
+
+    /// Inserts an object like `insert`, but accepts objects reaching past the bounds as long as
+    /// they overlap them, as if their edges were clamped to the bounds. Such objects can't fit
+    /// any quadrant and are stored in the root node. Queries skip everything outside the
+    /// bounds, so they only see the part of such an object inside the bounds: a query point or
+    /// region outside the bounds doesn't find it, even where the object reaches. `remove` and
+    /// `rebuild` keep working for them, while `update` treats them like `insert` does.
+    ///
+    /// Objects entirely outside the bounds return `QuadtreeError::OutOfBounds` with the edges
+    /// of the object and the bounds.
+    ///
+    /// # Examples
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use spatialize::quadtree::{Aabb, Bounded, Quadtree};
+    /// use spatialize::quadtree::QuadtreeError;
+    ///
+    /// let mut qt = Quadtree::new(-10.0, 10.0, 20.0, 20.0);
+    /// let road: Rc<dyn Bounded> = Rc::new(Aabb::new(5.0, -1.0, 15.0, 1.0));
+    /// assert_eq!(
+    ///     Err(QuadtreeError::OutOfBounds {
+    ///         object: (1.0, 15.0, -1.0, 5.0),
+    ///         bounds: (10.0, 10.0, -10.0, -10.0),
+    ///     }),
+    ///     qt.insert(Rc::clone(&road))
+    /// );
+    ///
+    /// qt.insert_clamped(Rc::clone(&road)).unwrap();
+    /// let mut hits: Vec<Rc<dyn Bounded>> = vec![];
+    /// qt.query_point(8.0, 0.0, &mut hits);
+    /// assert!(Rc::ptr_eq(&road, &hits[0]));
+    ///
+    /// // outside the bounds, the road isn't found
+    /// hits.clear();
+    /// qt.query_point(12.0, 0.0, &mut hits);
+    /// qt.get_rect(&Aabb::new(11.0, -1.0, 14.0, 1.0), &mut hits)
+    ///     .unwrap();
+    /// assert!(hits.is_empty());
+    /// assert!(qt.remove(&road).is_ok());
+    ///
+    /// let far_away = Rc::new(Aabb::new(20.0, -1.0, 22.0, 1.0));
+    /// assert!(qt.insert_clamped(far_away).is_err());
+    /// ```
+    ///
+    /// Clamped objects are found by `contains`, so a `Quadtree` rejecting duplicates rejects
+    /// them as well.
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use spatialize::quadtree::{Aabb, Bounded, Quadtree, QuadtreeError};
+    /// let mut qt = Quadtree::builder()
+    ///     .bounds(-10.0, 10.0, 20.0, 20.0)
+    ///     .dedup(true)
+    ///     .build();
+    /// let road: Rc<dyn Bounded> = Rc::new(Aabb::new(5.0, -1.0, 15.0, 1.0));
+    /// qt.insert_clamped(Rc::clone(&road)).unwrap();
+    ///
+    /// assert!(qt.contains(&road));
+    /// assert_eq!(
+    ///     Err(QuadtreeError::AlreadyPresent),
+    ///     qt.insert_clamped(Rc::clone(&road))
+    /// );
+    /// assert_eq!(1, qt.len());
+    /// ```
+    pub fn insert_clamped(
+        &mut self,
+        sized_object: Rc<dyn Bounded<T>>,
+    ) -> Result<(), QuadtreeError> {
+        self.insert_entry_clamped(&sized_object, Entry::strong(Rc::clone(&sized_object)))
+    }
+
+    /// Inserts an object implementing the `Bounded` trait by a weak reference, so the
+    /// `Quadtree` doesn't keep the object alive. Once every `Rc` of the object is dropped,
+    /// queries skip it, `len` no longer counts it, and its entry is removed the next time a
+    /// method taking `&mut self`, like `insert`, `remove`, `retain` or `prune`, passes by.
+    ///
+    /// # Examples
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use spatialize::quadtree::{Aabb, Bounded, Quadtree};
+    /// let mut qt = Quadtree::new(-10.0, 10.0, 20.0, 20.0);
+    /// let owned: Rc<dyn Bounded> = Rc::new(Aabb::new(0.0, -5.0, 5.0, 0.0));
+    /// qt.insert_weak(&owned).unwrap();
+    /// qt.insert(Rc::new(Aabb::new(-1.0, -1.0, 1.0, 1.0))).unwrap();
+    ///
+    /// let view = Aabb::new(-10.0, -10.0, 10.0, 10.0);
+    /// let mut result_vec: Vec<Rc<dyn Bounded>> = vec![];
+    /// qt.get_rect(&view, &mut result_vec).unwrap();
+    /// assert_eq!(2, result_vec.len());
+    ///
+    /// drop(result_vec);
+    /// drop(owned);
+    /// let mut result_vec: Vec<Rc<dyn Bounded>> = vec![];
+    /// qt.get_rect(&view, &mut result_vec).unwrap();
+    /// assert_eq!(1, result_vec.len());
+    /// assert_eq!(1, qt.len());
+    /// ```
+    pub fn insert_weak(&mut self, sized_object: &Rc<dyn Bounded<T>>) -> Result<(), QuadtreeError> {
+        self.insert_entry(sized_object, Entry::weak(sized_object))
+    }
+
+    /// Inserts an object implementing the `Bounded` trait like `insert`, tagging it with the
+    /// layer bitmask `mask`. `get_rect_masked` only returns the object for query masks sharing
+    /// a bit with `mask`, while all other queries ignore the mask. Objects inserted without a
+    /// mask belong to every layer. The mask is kept when the object is moved by `update` or a
+    /// restructuring of the `Quadtree`, but isn't serialized.
+    ///
+    /// # Examples
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use spatialize::quadtree::{Aabb, Bounded, Quadtree};
+    /// let mut qt = Quadtree::new(-10.0, 10.0, 20.0, 20.0);
+    /// qt.insert_with_mask(Rc::new(Aabb::new(0.0, -5.0, 5.0, 0.0)), 0b01).unwrap();
+    /// qt.insert(Rc::new(Aabb::new(-1.0, -1.0, 1.0, 1.0))).unwrap();
+    ///
+    /// let rect_view = Aabb::new(-10.0, -10.0, 10.0, 10.0);
+    /// let mut result_vec: Vec<Rc<dyn Bounded>> = vec![];
+    /// qt.get_rect_masked(&rect_view, 0b10, &mut result_vec);
+    /// assert_eq!(1, result_vec.len());
+    /// ```
+    pub fn insert_with_mask(
+        &mut self,
+        sized_object: Rc<dyn Bounded<T>>,
+        mask: u32,
+    ) -> Result<(), QuadtreeError> {
+        let mut entry = Entry::strong(Rc::clone(&sized_object));
+        entry.mask = mask;
+        self.insert_entry(&sized_object, entry)
+    }
+
+    /// Inserts an object implementing the `Bounded` trait like `insert` and returns an
+    /// `ObjectId` for it, which `get_by_id` and `remove_by_id` accept in place of the `Rc`.
+    ///
+    /// The `Quadtree` keeps an index from ids to objects. The node holding an object follows
+    /// from its edges, so looking it up by id first descends through the quadrants the object
+    /// fits in, which takes time proportional to the depth of the `Quadtree` rather than its
+    /// size. Only an object whose edges changed since it was placed is searched for in every
+    /// node.
+    ///
+    /// The index holds weak references, which don't keep objects alive but do keep their
+    /// allocations until the id is dropped. Ids of objects removed by other means than
+    /// `remove_by_id`, like `remove`, `retain` or `drain`, stay in the index until
+    /// `remove_by_id` is called with them or `compact_ids` purges them.
+    ///
+    /// # Examples
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use spatialize::quadtree::{Aabb, Bounded, Quadtree};
+    /// use spatialize::quadtree::QuadtreeError;
+    ///
+    /// let mut qt = Quadtree::new(-10.0, 10.0, 20.0, 20.0);
+    /// let id = qt
+    ///     .insert_with_id(Rc::new(Aabb::new(0.0, -5.0, 5.0, 0.0)))
+    ///     .unwrap();
+    /// let other = qt
+    ///     .insert_with_id(Rc::new(Aabb::new(-1.0, -1.0, 1.0, 1.0)))
+    ///     .unwrap();
+    /// assert_ne!(id, other);
+    ///
+    /// assert_eq!(5.0, qt.get_by_id(id).unwrap().east_edge());
+    /// assert_eq!(5.0, qt.remove_by_id(id).unwrap().east_edge());
+    /// assert!(qt.get_by_id(id).is_none());
+    /// assert_eq!(Err(QuadtreeError::NotFound), qt.remove_by_id(id).map(|_| ()));
+    /// assert_eq!(1, qt.len());
+    /// ```
+    pub fn insert_with_id(
+        &mut self,
+        sized_object: Rc<dyn Bounded<T>>,
+    ) -> Result<ObjectId, QuadtreeError> {
+        let weak = Rc::downgrade(&sized_object);
+        self.insert(sized_object)?;
+        let id = ObjectId(self.next_id);
+        self.next_id += 1;
+        self.ids.insert(id, weak);
+        Ok(id)
+    }
+
+    /// Returns the object inserted with `insert_with_id` under `id`, or `None` if it was
+    /// removed since. The object is found even if its edges changed since it was inserted.
+    pub fn get_by_id(&self, id: ObjectId) -> Option<Rc<dyn Bounded<T>>> {
+        self.ids
+            .get(&id)
+            .and_then(Weak::upgrade)
+            .filter(|sized_object| {
+                self.holds_at_edges(sized_object) || self.holds_anywhere(sized_object)
+            })
+    }
+
+    /// Removes the object inserted with `insert_with_id` under `id` and returns it, or returns
+    /// `QuadtreeError::NotFound` if it was removed already. The object is removed even if its
+    /// edges changed since it was inserted, and the id is dropped from the index either way.
+    ///
+    /// # Examples
+    /// ```
+    /// use std::cell::Cell;
+    /// use std::rc::Rc;
+    /// use spatialize::quadtree::{Bounded, Quadtree, QuadtreeError};
+    ///
+    /// #[derive(Debug)]
+    /// struct Entity {
+    ///     x: Cell<f32>,
+    /// }
+    ///
+    /// impl Bounded for Entity {
+    ///     fn north_edge(&self) -> f32 {
+    ///         1.0
+    ///     }
+    ///     fn east_edge(&self) -> f32 {
+    ///         self.x.get()
+    ///     }
+    ///     fn south_edge(&self) -> f32 {
+    ///         1.0
+    ///     }
+    ///     fn west_edge(&self) -> f32 {
+    ///         self.x.get()
+    ///     }
+    /// }
+    ///
+    /// let mut qt = Quadtree::with_capacity(-10.0, 10.0, 20.0, 20.0, 1);
+    /// let entity = Rc::new(Entity { x: Cell::new(-5.0) });
+    /// let id = qt.insert_with_id(entity.clone()).unwrap();
+    /// qt.insert_point(5.0, 5.0).unwrap();
+    ///
+    /// entity.x.set(5.0);
+    /// assert!(qt.get_by_id(id).is_some());
+    /// assert!(qt.remove_by_id(id).is_ok());
+    /// assert_eq!(1, qt.len());
+    /// assert_eq!(Err(QuadtreeError::NotFound), qt.remove_by_id(id).map(|_| ()));
+    /// ```
+    pub fn remove_by_id(&mut self, id: ObjectId) -> Result<Rc<dyn Bounded<T>>, QuadtreeError> {
+        let sized_object = self
+            .ids
+            .get(&id)
+            .and_then(Weak::upgrade)
+            .ok_or(QuadtreeError::NotFound);
+        let removed = sized_object.and_then(|sized_object| {
+            self.remove_anywhere(&sized_object)
+                .map(|_| sized_object)
+                .ok_or(QuadtreeError::NotFound)
+        });
+        // the id refers to nothing stored anymore, whether it was just removed or not
+        self.ids.remove(&id);
+        removed
+    }
+
+    /// Drops the ids of objects that are no longer stored, because they were removed by other
+    /// means than `remove_by_id` or dropped while stored by a weak reference, releasing the
+    /// index entries and the allocations they keep. Takes time proportional to the number of
+    /// stored objects and ids.
+    ///
+    /// # Examples
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use spatialize::quadtree::{Aabb, Bounded, Quadtree};
+    /// let mut qt = Quadtree::new(-10.0, 10.0, 20.0, 20.0);
+    /// let kept = qt.insert_with_id(Rc::new(Aabb::new(0.0, 0.0, 1.0, 1.0))).unwrap();
+    /// let sized_object: Rc<dyn Bounded> = Rc::new(Aabb::new(2.0, 2.0, 3.0, 3.0));
+    /// let removed = qt.insert_with_id(Rc::clone(&sized_object)).unwrap();
+    /// qt.remove(&sized_object).unwrap();
+    /// drop(sized_object);
+    ///
+    /// qt.compact_ids();
+    /// assert!(qt.get_by_id(kept).is_some());
+    /// assert!(qt.get_by_id(removed).is_none());
+    /// ```
+    pub fn compact_ids(&mut self) {
+        if self.ids.is_empty() {
+            return;
+        }
+        let stored: BTreeSet<*const ()> = self
+            .iter()
+            .map(|sized_object| Rc::as_ptr(&sized_object) as *const ())
+            .collect();
+        self.ids
+            .retain(|_, weak| stored.contains(&(weak.as_ptr() as *const ())));
+    }
+
+    /// Inserts `sized_object` under `id` if no object is stored under it, or moves the stored
+    /// object to match the current edges of `sized_object` like `update`, replacing it if it's
+    /// a different object. Returns whether the object was inserted or updated, which keeps the
+    /// `Quadtree` in sync with a changing set of entities in one call.
+    ///
+    /// Like with `update`, an object no longer fitting within the bounds stays removed and
+    /// `QuadtreeError::OutOfBounds` is returned.
+    ///
+    /// # Examples
+    /// ```
+    /// use std::cell::Cell;
+    /// use std::rc::Rc;
+    /// use spatialize::quadtree::{Bounded, ObjectId, Quadtree, Upsert};
+    ///
+    /// #[derive(Debug)]
+    /// struct Entity {
+    ///     x: Cell<f32>,
+    ///     y: Cell<f32>,
+    /// }
+    ///
+    /// impl Bounded for Entity {
+    ///     fn north_edge(&self) -> f32 {
+    ///         self.y.get()
+    ///     }
+    ///     fn east_edge(&self) -> f32 {
+    ///         self.x.get()
+    ///     }
+    ///     fn south_edge(&self) -> f32 {
+    ///         self.y.get()
+    ///     }
+    ///     fn west_edge(&self) -> f32 {
+    ///         self.x.get()
+    ///     }
+    /// }
+    ///
+    /// let mut qt = Quadtree::new(-10.0, 10.0, 20.0, 20.0);
+    /// let entity = Rc::new(Entity { x: Cell::new(-5.0), y: Cell::new(5.0) });
+    /// let id = ObjectId::from(42);
+    /// assert_eq!(Ok(Upsert::Inserted), qt.upsert(id, entity.clone()));
+    ///
+    /// entity.x.set(5.0);
+    /// assert_eq!(Ok(Upsert::Updated), qt.upsert(id, entity.clone()));
+    /// assert_eq!(1, qt.len());
+    /// let mut hits: Vec<Rc<dyn Bounded>> = vec![];
+    /// qt.query_point(5.0, 5.0, &mut hits);
+    /// assert_eq!(1, hits.len());
+    ///
+    /// let other = qt.insert_with_id(Rc::new(Entity { x: Cell::new(0.0), y: Cell::new(0.0) }));
+    /// assert!(other.unwrap() > id);
+    /// ```
+    pub fn upsert(
+        &mut self,
+        id: ObjectId,
+        sized_object: Rc<dyn Bounded<T>>,
+    ) -> Result<Upsert, QuadtreeError> {
+        let previous = self.ids.get(&id).and_then(Weak::upgrade);
+        if let Some(entry) = previous.and_then(|previous| self.remove_anywhere(&previous)) {
+            let entry = if entry.is(&sized_object) {
+                entry
+            } else {
+                Entry {
+                    reference: Reference::Strong(Rc::clone(&sized_object)),
+                    mask: entry.mask,
+                }
+            };
+            self.ids.insert(id, Rc::downgrade(&sized_object));
+            return self
+                .insert_entry(&sized_object, entry)
+                .map(|()| Upsert::Updated);
+        }
+        self.insert(Rc::clone(&sized_object))?;
+        self.ids.insert(id, Rc::downgrade(&sized_object));
+        self.next_id = self.next_id.max(id.0.saturating_add(1));
+        Ok(Upsert::Inserted)
+    }
+
+    /// A private function checking whether `sized_object` is stored in one of the nodes its
+    /// edges fit in, which is where `insert` places it.
+    fn holds_at_edges(&self, sized_object: &Rc<dyn Bounded<T>>) -> bool {
+        self.fits(&**sized_object)
+            && (self.contents.iter().any(|entry| entry.is(sized_object))
+                || self
+                    .quadrants()
+                    .any(|rc_ref| rc_ref.borrow().holds_at_edges(sized_object)))
+    }
+
+    /// A private function checking whether `sized_object` is stored in any node, regardless of
+    /// its edges.
+    fn holds_anywhere(&self, sized_object: &Rc<dyn Bounded<T>>) -> bool {
+        self.contents.iter().any(|entry| entry.is(sized_object))
+            || self
+                .quadrants()
+                .any(|rc_ref| rc_ref.borrow().holds_anywhere(sized_object))
+    }
+
+    /// A private function doing the insertion of `entry`, which refers to `sized_object`.
+    fn insert_entry(
+        &mut self,
+        sized_object: &Rc<dyn Bounded<T>>,
+        entry: Entry<T>,
+    ) -> Result<(), QuadtreeError> {
+        if self.fits(&**sized_object) {
+            //Object fits in Quadtree
+            self.place_entry(sized_object, entry)
+        } else {
+            Err(out_of_bounds(&**sized_object, &self.loose_edges()))
+        }
+    }
+
+    /// A private function doing the insertion of `entry` like `insert_entry`, also accepting
+    /// objects that only overlap the bounds, which end up in this node.
+    fn insert_entry_clamped(
+        &mut self,
+        sized_object: &Rc<dyn Bounded<T>>,
+        entry: Entry<T>,
+    ) -> Result<(), QuadtreeError> {
+        if self.intersects(&**sized_object) {
+            self.place_entry(sized_object, entry)
+        } else {
+            Err(out_of_bounds(&**sized_object, &self.loose_edges()))
+        }
+    }
+
+    /// A private function placing `entry`, which refers to `sized_object`, into this node or
+    /// the quadrants it fits in.
+    fn place_entry(
+        &mut self,
+        sized_object: &Rc<dyn Bounded<T>>,
+        entry: Entry<T>,
+    ) -> Result<(), QuadtreeError> {
+        if self.dedup && self.contains(sized_object) {
+            return Err(QuadtreeError::AlreadyPresent);
+        }
+        let mut pending = VecDeque::new();
+        self.place(entry, &mut pending);
+        // first in, first out, so every quadrant receives its objects in insertion order
+        while let Some((rc_ref, entry)) = pending.pop_front() {
+            rc_ref.borrow_mut().place(entry, &mut pending);
+        }
+        Ok(())
+    }
+
+    /// Inserts a `Point` at `(x, y)`, returning the stored object so it can be removed later.
+    /// Points never stop fitting into smaller quadrants, so they are stored at the maximum
+    /// depth unless the node capacity keeps them higher up.
+    ///
+    /// # Examples
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use spatialize::quadtree::{Bounded, Quadtree};
+    /// let mut qt = Quadtree::new(-10.0, 10.0, 20.0, 20.0);
+    /// for _ in 0..1000 {
+    ///     qt.insert_point(1.0, 1.0).unwrap();
+    /// }
+    /// assert_eq!(1000, qt.len());
+    ///
+    /// let mut hits: Vec<Rc<dyn Bounded>> = vec![];
+    /// qt.query_point(1.0, 1.0, &mut hits);
+    /// assert_eq!(1000, hits.len());
+    /// ```
+    pub fn insert_point(&mut self, x: T, y: T) -> Result<Rc<dyn Bounded<T>>, QuadtreeError>
+    where
+        T: Debug + 'static,
+    {
+        let point: Rc<dyn Bounded<T>> = Rc::new(Point::new(x, y));
+        self.insert(Rc::clone(&point))?;
+        Ok(point)
+    }
+
+    /// Inserts an object implementing the `Bounded` trait unless the same `Rc` is already
+    /// stored, in which case `QuadtreeError::AlreadyPresent` is returned.
+    ///
+    /// # Examples
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use spatialize::quadtree::{Aabb, Bounded, Quadtree};
+    /// # use spatialize::quadtree::QuadtreeError;
+    /// let mut qt = Quadtree::new(-10.0, 10.0, 20.0, 20.0);
+    /// let sized_object: Rc<dyn Bounded> = Rc::new(Aabb::new(0.0, -5.0, 5.0, 0.0));
+    /// assert!(qt.insert_unique(Rc::clone(&sized_object)).is_ok());
+    /// assert_eq!(
+    ///     Err(QuadtreeError::AlreadyPresent),
+    ///     qt.insert_unique(Rc::clone(&sized_object))
+    /// );
+    /// assert_eq!(1, qt.len());
+    /// ```
+    pub fn insert_unique(&mut self, sized_object: Rc<dyn Bounded<T>>) -> Result<(), QuadtreeError> {
+        if self.contains(&sized_object) {
+            Err(QuadtreeError::AlreadyPresent)
+        } else {
+            self.insert(sized_object)
+        }
+    }
+
+    /// Returns `true` if `sized_object` is stored in the `Quadtree`, identified by pointer
+    /// equality. Like `remove`, only the quadrants the object currently fits in are searched,
+    /// along with the root for objects reaching past it, so an object whose edges changed
+    /// since it was inserted may not be found.
+    ///
+    /// # Examples
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use spatialize::quadtree::{Aabb, Bounded, Quadtree};
+    /// let mut qt = Quadtree::new(-10.0, 10.0, 20.0, 20.0);
+    /// let sized_object: Rc<dyn Bounded> = Rc::new(Aabb::new(0.0, -5.0, 5.0, 0.0));
+    /// let twin: Rc<dyn Bounded> = Rc::new(Aabb::new(0.0, -5.0, 5.0, 0.0));
+    /// qt.insert(Rc::clone(&sized_object)).unwrap();
+    /// assert!(qt.contains(&sized_object));
+    /// assert!(!qt.contains(&twin));
+    /// ```
+    pub fn contains(&self, sized_object: &Rc<dyn Bounded<T>>) -> bool {
+        // objects inserted with `insert_clamped` may reach past the root
+        (self.fits(&**sized_object) || self.depth == 0 && self.intersects(&**sized_object))
+            && (self.contents.iter().any(|entry| entry.is(sized_object))
+                || self
+                    .quadrants()
+                    .any(|rc_ref| rc_ref.borrow().contains(sized_object)))
+    }
+
+    /// Inserts an object implementing the `Bounded` trait, growing the `Quadtree` until the
+    /// object fits within its bounds.
+    ///
+    /// Each growth step doubles the root in the direction of the object and keeps the old root
+    /// as one of the quadrants of the new, larger root. The maximum depth grows along with the
+    /// tree, so the smallest possible quadrant size stays the same. Objects with non-finite
+    /// edges can't be fitted, and trees without an area can't grow. If the grown bounds would
+    /// no longer be finite, the tree stops growing and `QuadtreeError::OutOfBounds` is
+    /// returned.
+    ///
+    /// # Examples
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use spatialize::quadtree::{Aabb, Bounded, Quadtree};
+    /// let mut qt = Quadtree::new(-10.0, 10.0, 20.0, 20.0);
+    /// qt.insert(Rc::new(Aabb::new(0.0, -5.0, 5.0, 0.0))).unwrap();
+    /// qt.insert_growing(Rc::new(Aabb::new(25.0, -35.0, 30.0, -30.0))).unwrap();
+    /// assert_eq!((-10.0, 10.0, 80.0, 80.0), qt.bounds());
+    ///
+    /// let rect_view = Aabb::new(-10.0, -70.0, 70.0, 10.0);
+    /// let mut result_vec: Vec<Rc<dyn Bounded>> = vec![];
+    /// qt.get_rect(&rect_view, &mut result_vec).unwrap();
+    /// assert_eq!(2, result_vec.len());
+    ///
+    /// let far_away = Rc::new(Aabb::new(-3.3e38, 0.0, -3.2e38, 1.0));
+    /// assert!(qt.insert_growing(far_away).is_err());
+    /// ```
+    pub fn insert_growing(
+        &mut self,
+        sized_object: Rc<dyn Bounded<T>>,
+    ) -> Result<(), QuadtreeError> {
+        let finite = [
+            sized_object.north_edge(),
+            sized_object.east_edge(),
+            sized_object.south_edge(),
+            sized_object.west_edge(),
+        ]
+        .iter()
+        .all(|edge| edge.is_finite());
+        if finite && self.width > T::zero() && self.height > T::zero() {
+            while !self.fits(&*sized_object) {
+                let west = sized_object.west_edge() < self.position_x;
+                let north = sized_object.north_edge() > self.position_y;
+                if !self.can_grow(west, north) {
+                    return Err(out_of_bounds(&*sized_object, &self.loose_edges()));
+                }
+                self.grow(west, north);
+            }
+        }
+        self.insert(sized_object)
+    }
+
+    /// A private function checking whether the edges of the `Quadtree` stay finite when it
+    /// grows towards the west or east and the north or south.
+    fn can_grow(&self, west: bool, north: bool) -> bool {
+        let width = self.width * two();
+        let height = self.height * two();
+        let position_x = if west {
+            self.position_x - self.width
+        } else {
+            self.position_x
+        };
+        let position_y = if north {
+            self.position_y + self.height
+        } else {
+            self.position_y
+        };
+        [
+            position_x + width,
+            position_y - height,
+            position_x,
+            position_y,
+        ]
+        .iter()
+        .all(|edge| edge.is_finite())
+    }
+
+    /// A private function doubling the size of the `Quadtree` towards the west or east and the
+    /// north or south, moving the current root into the matching quadrant of the new root.
+    fn grow(&mut self, west: bool, north: bool) {
+        let position_x = if west {
+            self.position_x - self.width
+        } else {
+            self.position_x
+        };
+        let position_y = if north {
+            self.position_y + self.height
+        } else {
+            self.position_y
+        };
+        let mut root = Quadtree::with_limits(
+            position_x,
+            position_y,
+            self.width * two(),
+            self.height * two(),
+            self.capacity,
+            self.max_depth + 1,
+        );
+        root.depth = self.depth;
+        root.dedup = self.dedup;
+        root.looseness = self.looseness;
+        root.hooks = self.hooks.clone();
+
+        let mut old_root = core::mem::replace(self, root);
+        self.ids = core::mem::take(&mut old_root.ids);
+        self.next_id = old_root.next_id;
+        old_root.deepen();
+        let old_root = Rc::new(RefCell::new(old_root));
+        let half_width = self.width / two();
+        let half_height = self.height / two();
+        let quadrant = |is_old_root: bool, position_x: T, position_y: T| {
+            if is_old_root {
+                Rc::clone(&old_root)
+            } else {
+                self.quadrant(position_x, position_y)
+            }
+        };
+        let northeast = quadrant(
+            west && !north,
+            self.position_x + half_width,
+            self.position_y,
+        );
+        let northwest = quadrant(!west && !north, self.position_x, self.position_y);
+        let southeast = quadrant(
+            west && north,
+            self.position_x + half_width,
+            self.position_y - half_height,
+        );
+        let southwest = quadrant(
+            !west && north,
+            self.position_x,
+            self.position_y - half_height,
+        );
+        self.northeast_quad = Some(northeast);
+        self.northwest_quad = Some(northwest);
+        self.southeast_quad = Some(southeast);
+        self.southwest_quad = Some(southwest);
+        self.divided = true;
+    }
+
+    /// A private function moving this node and all its descendants one level deeper.
+    fn deepen(&mut self) {
+        self.depth += 1;
+        self.max_depth += 1;
+        for rc_ref in self.quadrants() {
+            rc_ref.borrow_mut().deepen();
+        }
+    }
+
+    /// Calls `f` for every object overlapping `rect`, walking the `Quadtree` like `get_rect` but
+    /// without collecting the objects into a vector.
+    ///
+    /// # Examples
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use spatialize::quadtree::{Aabb, Bounded, Quadtree};
+    /// let mut qt = Quadtree::new(-10.0, 10.0, 20.0, 20.0);
+    /// qt.insert(Rc::new(Aabb::new(0.0, -5.0, 5.0, 0.0))).unwrap();
+    /// qt.insert(Rc::new(Aabb::new(-9.0, 7.0, -7.0, 9.0))).unwrap();
+    ///
+    /// let mut area = 0.0;
+    /// qt.for_each_in_rect(&Aabb::new(-2.0, -8.0, 8.0, 2.0), |rc| {
+    ///     area += (rc.east_edge() - rc.west_edge()) * (rc.north_edge() - rc.south_edge());
+    /// });
+    /// assert_eq!(25.0, area);
+    /// ```
+    pub fn for_each_in_rect<F: FnMut(&Rc<dyn Bounded<T>>)>(&self, rect: &dyn Bounded<T>, mut f: F) {
+        self.visit_rect(rect, &mut |_, rc| f(rc));
+    }
+
+    /// A private function doing the traversal of `for_each_in_rect`, passing each overlapping
+    /// object along with its entry.
+    fn visit_rect<F: FnMut(&Entry<T>, &Rc<dyn Bounded<T>>)>(
+        &self,
+        rect: &dyn Bounded<T>,
+        f: &mut F,
+    ) {
+        if self.intersects(rect) {
+            for rc_ref in self.quadrants() {
+                rc_ref.borrow().visit_rect(rect, f);
+            }
+            for entry in self.contents.iter() {
+                entry.with(|rc| {
+                    if rc.intersects(rect) {
+                        f(entry, rc);
+                    }
+                });
+            }
+        }
+    }
+
+    /// Pushes every object matching `rect` according to `mode` into `out`.
+    ///
+    /// # Examples
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use spatialize::quadtree::{Aabb, Bounded, Quadtree};
+    /// use spatialize::quadtree::QueryMode;
+    ///
+    /// let mut qt = Quadtree::new(-10.0, 10.0, 20.0, 20.0);
+    /// let inside: Rc<dyn Bounded> = Rc::new(Aabb::new(-4.0, 2.0, -2.0, 4.0));
+    /// let straddling: Rc<dyn Bounded> = Rc::new(Aabb::new(4.0, -1.0, 6.0, 1.0));
+    /// let enclosing: Rc<dyn Bounded> = Rc::new(Aabb::new(-6.0, -6.0, 6.0, 6.0));
+    /// qt.insert(Rc::clone(&inside)).unwrap();
+    /// qt.insert(Rc::clone(&straddling)).unwrap();
+    /// qt.insert(Rc::clone(&enclosing)).unwrap();
+    ///
+    /// let rect_view = Aabb::new(-5.0, -5.0, 5.0, 5.0);
+    /// let query = |mode| {
+    ///     let mut result_vec: Vec<Rc<dyn Bounded>> = vec![];
+    ///     qt.get_rect_mode(&rect_view, mode, &mut result_vec);
+    ///     result_vec
+    /// };
+    ///
+    /// assert_eq!(3, query(QueryMode::Overlap).len());
+    ///
+    /// let contained = query(QueryMode::Contains);
+    /// assert_eq!(1, contained.len());
+    /// assert!(Rc::ptr_eq(&inside, &contained[0]));
+    ///
+    /// let enclosed_by = query(QueryMode::Within);
+    /// assert_eq!(1, enclosed_by.len());
+    /// assert!(Rc::ptr_eq(&enclosing, &enclosed_by[0]));
+    /// ```
+    pub fn get_rect_mode(
+        &self,
+        rect: &dyn Bounded<T>,
+        mode: QueryMode,
+        out: &mut Vec<Rc<dyn Bounded<T>>>,
+    ) {
+        self.for_each_in_rect(rect, |rc| {
+            let matches = match mode {
+                QueryMode::Overlap => true,
+                QueryMode::Contains => encloses(rect, &**rc),
+                QueryMode::Within => encloses(&**rc, rect),
+            };
+            if matches {
+                out.push(Rc::clone(rc));
+            }
+        });
+    }
+
+    /// Pushes every object overlapping `rect` whose layer mask shares a bit with `mask` into
+    /// `out`. Objects inserted with `insert_with_mask` carry the given mask, all others belong
+    /// to every layer and match any non-zero `mask`.
+    ///
+    /// # Examples
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use spatialize::quadtree::{Aabb, Bounded, Quadtree};
+    /// const TERRAIN: u32 = 1 << 0;
+    /// const ENEMIES: u32 = 1 << 1;
+    /// const PICKUPS: u32 = 1 << 2;
+    ///
+    /// let mut qt = Quadtree::new(-10.0, 10.0, 20.0, 20.0);
+    /// let ground: Rc<dyn Bounded> = Rc::new(Aabb::new(-9.0, -9.0, 9.0, -5.0));
+    /// let enemy: Rc<dyn Bounded> = Rc::new(Aabb::new(1.0, 0.0, 2.0, 1.0));
+    /// let coin: Rc<dyn Bounded> = Rc::new(Aabb::new(-2.0, 1.0, -1.0, 2.0));
+    /// qt.insert_with_mask(Rc::clone(&ground), TERRAIN).unwrap();
+    /// qt.insert_with_mask(Rc::clone(&enemy), ENEMIES).unwrap();
+    /// qt.insert_with_mask(Rc::clone(&coin), PICKUPS).unwrap();
+    ///
+    /// let rect_view = Aabb::new(-10.0, -10.0, 10.0, 10.0);
+    /// let mut result_vec: Vec<Rc<dyn Bounded>> = vec![];
+    /// qt.get_rect_masked(&rect_view, ENEMIES | PICKUPS, &mut result_vec);
+    /// assert_eq!(2, result_vec.len());
+    /// assert!(!result_vec.iter().any(|rc| Rc::ptr_eq(rc, &ground)));
+    ///
+    /// let mut result_vec: Vec<Rc<dyn Bounded>> = vec![];
+    /// qt.get_rect_masked(&rect_view, TERRAIN, &mut result_vec);
+    /// assert_eq!(1, result_vec.len());
+    /// assert!(Rc::ptr_eq(&ground, &result_vec[0]));
+    /// ```
+    pub fn get_rect_masked(
+        &self,
+        rect: &dyn Bounded<T>,
+        mask: u32,
+        out: &mut Vec<Rc<dyn Bounded<T>>>,
+    ) {
+        self.visit_rect(rect, &mut |entry, rc| {
+            if entry.mask & mask != 0 {
+                out.push(Rc::clone(rc));
+            }
+        });
+    }
+
+    /// Pushes every object whose edges `start` touches while moving by `(dx, dy)` into `out`.
+    /// Both quadrants and objects are checked against the exact path of `start`, so only
+    /// quadrants it passes through are visited and fast objects can't tunnel through anything
+    /// between two positions.
+    ///
+    /// # Examples
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use spatialize::quadtree::{Aabb, Bounded, Quadtree};
+    /// let mut qt = Quadtree::new(-10.0, 10.0, 20.0, 20.0);
+    /// let wall: Rc<dyn Bounded> = Rc::new(Aabb::new(0.0, -1.0, 0.1, 1.0));
+    /// qt.insert(Rc::clone(&wall)).unwrap();
+    ///
+    /// let bullet = Aabb::new(-5.0, 0.0, -4.9, 0.1);
+    /// let mut at_start: Vec<Rc<dyn Bounded>> = vec![];
+    /// qt.get_rect(&Aabb::new(-5.0, 0.0, -4.9, 0.1), &mut at_start).unwrap();
+    /// let mut at_end: Vec<Rc<dyn Bounded>> = vec![];
+    /// qt.get_rect(&Aabb::new(5.0, 0.0, 5.1, 0.1), &mut at_end).unwrap();
+    /// assert!(at_start.is_empty() && at_end.is_empty());
+    ///
+    /// let mut hits: Vec<Rc<dyn Bounded>> = vec![];
+    /// qt.query_swept(&bullet, 10.0, 0.0, &mut hits);
+    /// assert_eq!(1, hits.len());
+    /// assert!(Rc::ptr_eq(&wall, &hits[0]));
+    ///
+    /// // passing diagonally by an object only inside the enclosing box isn't a hit
+    /// qt.insert(Rc::new(Aabb::new(1.0, 1.0, 2.0, 2.0))).unwrap();
+    /// let mut hits: Vec<Rc<dyn Bounded>> = vec![];
+    /// qt.query_swept(&Aabb::new(1.0, 6.0, 2.0, 7.0), 5.0, -5.0, &mut hits);
+    /// assert!(hits.is_empty());
+    /// ```
+    pub fn query_swept(
+        &self,
+        start: &dyn Bounded<T>,
+        dx: T,
+        dy: T,
+        out: &mut Vec<Rc<dyn Bounded<T>>>,
+    ) {
+        self.visit_swept(start, dx, dy, &mut |rc, _| out.push(Rc::clone(rc)));
+    }
+
+    /// Pushes every object whose edges the line segment from `(x0, y0)` to `(x1, y1)` passes
+    /// through into `out`, ordered by where the segment first touches them, starting at
+    /// `(x0, y0)`. Only quadrants the segment crosses are visited.
+    ///
+    /// # Examples
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use spatialize::quadtree::{Aabb, Bounded, Quadtree};
+    /// let mut qt = Quadtree::new(-10.0, 10.0, 20.0, 20.0);
+    /// let far: Rc<dyn Bounded> = Rc::new(Aabb::new(-3.5, 5.5, -2.5, 6.5));
+    /// let near: Rc<dyn Bounded> = Rc::new(Aabb::new(-8.0, -1.5, -7.0, -0.5));
+    /// qt.insert(Rc::clone(&far)).unwrap();
+    /// qt.insert(Rc::clone(&near)).unwrap();
+    /// qt.insert(Rc::new(Aabb::new(-9.0, 8.0, -8.0, 9.0))).unwrap();
+    /// qt.insert(Rc::new(Aabb::new(5.0, 4.0, 6.0, 5.0))).unwrap();
+    /// qt.insert(Rc::new(Aabb::new(5.0, -6.0, 6.0, -5.0))).unwrap();
+    ///
+    /// // from the southwest into the northwest quadrant
+    /// let mut hits: Vec<Rc<dyn Bounded>> = vec![];
+    /// qt.query_segment(-8.0, -2.0, -2.0, 8.0, &mut hits);
+    /// assert_eq!(2, hits.len());
+    /// assert!(Rc::ptr_eq(&near, &hits[0]));
+    /// assert!(Rc::ptr_eq(&far, &hits[1]));
+    /// ```
+    pub fn query_segment(&self, x0: T, y0: T, x1: T, y1: T, out: &mut Vec<Rc<dyn Bounded<T>>>)
+    where
+        T: Debug,
+    {
+        let mut hits = vec![];
+        self.visit_swept(&Point::new(x0, y0), x1 - x0, y1 - y0, &mut |rc, enter| {
+            hits.push(ByDistance(enter, Rc::clone(rc)))
+        });
+        hits.sort();
+        out.extend(hits.into_iter().map(|ByDistance(_, rc)| rc));
+    }
+
+    /// Returns the first object the ray from `(origin_x, origin_y)` in the direction
+    /// `(dir_x, dir_y)` enters within `max_dist`, together with the distance `t` along the ray
+    /// at which it enters, i.e. the object is hit at `origin + t * dir`. Objects enclosing the
+    /// origin are hit at `0`. `max_dist` has to be finite.
+    ///
+    /// Quadrants are visited in the order the ray enters them, and the search stops as soon as
+    /// no remaining quadrant is entered before the closest hit found so far.
+    ///
+    /// # Examples
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use spatialize::quadtree::{Aabb, Bounded, Quadtree};
+    /// let mut qt = Quadtree::new(-10.0, 10.0, 20.0, 20.0);
+    /// let far: Rc<dyn Bounded> = Rc::new(Aabb::new(6.0, -1.0, 8.0, 1.0));
+    /// let near: Rc<dyn Bounded> = Rc::new(Aabb::new(-4.0, -1.0, -2.0, 1.0));
+    /// qt.insert(Rc::clone(&far)).unwrap();
+    /// qt.insert(Rc::clone(&near)).unwrap();
+    ///
+    /// let (hit, t) = qt.raycast(-9.0, 0.0, 1.0, 0.0, 100.0).unwrap();
+    /// assert!(Rc::ptr_eq(&near, &hit));
+    /// assert_eq!(5.0, t);
+    ///
+    /// let (hit, t) = qt.raycast(9.0, 0.0, -1.0, 0.0, 100.0).unwrap();
+    /// assert!(Rc::ptr_eq(&far, &hit));
+    /// assert_eq!(1.0, t);
+    ///
+    /// assert!(qt.raycast(-9.0, 0.0, 1.0, 0.0, 4.0).is_none());
+    /// assert!(qt.raycast(-9.0, 0.0, 0.0, 1.0, 100.0).is_none());
+    /// ```
+    pub fn raycast(
+        &self,
+        origin_x: T,
+        origin_y: T,
+        dir_x: T,
+        dir_y: T,
+        max_dist: T,
+    ) -> Option<(Rc<dyn Bounded<T>>, T)>
+    where
+        T: Debug,
+    {
+        let origin = Point::new(origin_x, origin_y);
+        let (dx, dy) = (dir_x * max_dist, dir_y * max_dist);
+        let mut hit = None;
+        let mut pending = BinaryHeap::new();
+        self.collect_raycast(&origin, dx, dy, &mut hit, &mut pending);
+        while let Some(Reverse(ByDistance(enter, rc_ref))) = pending.pop() {
+            if hit
+                .as_ref()
+                .is_some_and(|closest: &ByDistance<T, _>| enter > closest.0)
+            {
+                break;
+            }
+            rc_ref
+                .borrow()
+                .collect_raycast(&origin, dx, dy, &mut hit, &mut pending);
+        }
+        hit.map(|ByDistance(enter, rc)| (rc, enter * max_dist))
+    }
+
+    /// A private function keeping the object of this node the ray enters first in `hit` if it
+    /// is entered before the current one, and queueing the quadrants the ray enters in
+    /// `pending` by the time it enters them.
+    fn collect_raycast(
+        &self,
+        origin: &Point<T>,
+        dx: T,
+        dy: T,
+        hit: &mut Option<ByDistance<T, Rc<dyn Bounded<T>>>>,
+        pending: &mut BinaryHeap<Reverse<ByDistance<T, QuadrantRef<T>>>>,
+    ) where
+        T: Debug,
+    {
+        for rc in self.node_contents() {
+            if let Some(enter) = sweep_entry(
+                origin,
+                dx,
+                dy,
+                rc.north_edge(),
+                rc.east_edge(),
+                rc.south_edge(),
+                rc.west_edge(),
+            ) {
+                if hit.as_ref().is_none_or(|closest| enter < closest.0) {
+                    *hit = Some(ByDistance(enter, rc));
+                }
+            }
+        }
+        for rc_ref in self.quadrants() {
+            let edges = rc_ref.borrow().loose_edges();
+            if let Some(enter) = sweep_entry(
+                origin,
+                dx,
+                dy,
+                edges.north,
+                edges.east,
+                edges.south,
+                edges.west,
+            ) {
+                pending.push(Reverse(ByDistance(enter, Rc::clone(rc_ref))));
+            }
+        }
+    }
+
+    /// A private function calling `f` with every object `moving` touches while it moves by
+    /// `(dx, dy)`, together with the time in `[0, 1]` it first touches the object.
+    fn visit_swept<F: FnMut(&Rc<dyn Bounded<T>>, T)>(
+        &self,
+        moving: &dyn Bounded<T>,
+        dx: T,
+        dy: T,
+        f: &mut F,
+    ) {
+        let edges = self.loose_edges();
+        let touched = sweep_entry(
+            moving,
+            dx,
+            dy,
+            edges.north,
+            edges.east,
+            edges.south,
+            edges.west,
+        );
+        if touched.is_some() {
+            for rc_ref in self.quadrants() {
+                rc_ref.borrow().visit_swept(moving, dx, dy, f);
+            }
+            for rc in self.node_contents() {
+                if let Some(enter) = sweep_entry(
+                    moving,
+                    dx,
+                    dy,
+                    rc.north_edge(),
+                    rc.east_edge(),
+                    rc.south_edge(),
+                    rc.west_edge(),
+                ) {
+                    f(&rc, enter);
+                }
+            }
+        }
+    }
+
+    /// Removes an object previously inserted into the `Quadtree`, identified by pointer equality.
+    /// Only the quadrants the object currently fits in are searched.
+    ///
+    /// # Examples
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use spatialize::quadtree::{Aabb, Bounded, Quadtree, QuadtreeError};
+    /// let mut qt = Quadtree::new(-10.0, 10.0, 20.0, 20.0);
+    /// let sized_object: Rc<dyn Bounded> = Rc::new(Aabb::new(0.0, -5.0, 5.0, 0.0));
+    /// qt.insert(Rc::clone(&sized_object)).unwrap();
+    /// assert!(qt.remove(&sized_object).is_ok());
+    /// assert_eq!(Err(QuadtreeError::NotFound), qt.remove(&sized_object));
+    ///
+    /// let rect_view = Aabb::new(-10.0, -10.0, 10.0, 10.0);
+    /// let mut result_vec: Vec<Rc<dyn Bounded>> = vec![];
+    /// qt.get_rect(&rect_view, &mut result_vec).unwrap();
+    /// assert!(result_vec.is_empty());
+    /// ```
+    pub fn remove(&mut self, sized_object: &Rc<dyn Bounded<T>>) -> Result<(), QuadtreeError> {
+        // objects inserted with `insert_clamped` may reach past the root
+        if self.fits(&**sized_object) || self.depth == 0 && self.intersects(&**sized_object) {
+            self.contents.retain(|entry| entry.is_alive());
+            if let Some(index) = self
+                .contents
+                .iter()
+                .position(|entry| entry.is(sized_object))
+            {
+                self.contents.remove(index);
+                return Ok(());
+            }
+            for rc_ref in self.quadrants() {
+                if rc_ref.borrow_mut().remove(sized_object).is_ok() {
+                    return Ok(());
+                }
+            }
+        }
+        Err(QuadtreeError::NotFound)
+    }
+
+    /// Moves an object to the quadrant matching its current edges, which may have changed since
+    /// it was inserted.
+    ///
+    /// The object is first removed from whichever node it is stored in, searching the whole
+    /// `Quadtree`, and then inserted again. If it no longer fits within the bounds, it stays
+    /// removed and `QuadtreeError::OutOfBounds` is returned.
+    ///
+    /// # Examples
+    /// ```
+    /// use std::cell::Cell;
+    /// use std::rc::Rc;
+    /// use spatialize::quadtree::{Bounded, Quadtree, QuadtreeError};
+    ///
+    /// #[derive(Debug)]
+    /// struct Particle {
+    ///     x: Cell<f32>,
+    ///     y: Cell<f32>,
+    /// }
+    ///
+    /// impl Bounded for Particle {
+    ///     fn north_edge(&self) -> f32 {
+    ///         self.y.get()
+    ///     }
+    ///     fn east_edge(&self) -> f32 {
+    ///         self.x.get()
+    ///     }
+    ///     fn south_edge(&self) -> f32 {
+    ///         self.y.get()
+    ///     }
+    ///     fn west_edge(&self) -> f32 {
+    ///         self.x.get()
+    ///     }
+    /// }
+    ///
+    /// let mut qt = Quadtree::new(-10.0, 10.0, 20.0, 20.0);
+    /// let particle = Rc::new(Particle { x: Cell::new(-5.0), y: Cell::new(5.0) });
+    /// let sized_object: Rc<dyn Bounded> = particle.clone();
+    /// qt.insert(Rc::clone(&sized_object)).unwrap();
+    ///
+    /// particle.x.set(5.0);
+    /// particle.y.set(-5.0);
+    /// qt.update(&sized_object).unwrap();
+    /// let mut hits: Vec<Rc<dyn Bounded>> = vec![];
+    /// qt.query_point(5.0, -5.0, &mut hits);
+    /// assert_eq!(1, hits.len());
+    ///
+    /// particle.x.set(50.0);
+    /// assert!(matches!(
+    ///     qt.update(&sized_object),
+    ///     Err(QuadtreeError::OutOfBounds { .. })
+    /// ));
+    /// assert!(qt.is_empty());
+    /// ```
+    pub fn update(&mut self, sized_object: &Rc<dyn Bounded<T>>) -> Result<(), QuadtreeError> {
+        match self.remove_anywhere(sized_object) {
+            Some(entry) => self.insert_entry(sized_object, entry),
+            None => Err(QuadtreeError::NotFound),
+        }
+    }
+
+    /// A private function removing `sized_object` from any node, regardless of its edges, and
+    /// returning its entry.
+    fn remove_anywhere(&mut self, sized_object: &Rc<dyn Bounded<T>>) -> Option<Entry<T>> {
+        self.contents.retain(|entry| entry.is_alive());
+        if let Some(index) = self
+            .contents
+            .iter()
+            .position(|entry| entry.is(sized_object))
+        {
+            return Some(self.contents.remove(index));
+        }
+        self.quadrants()
+            .find_map(|rc_ref| rc_ref.borrow_mut().remove_anywhere(sized_object))
+    }
+
+    /// Keeps only the objects for which `f` returns `true`, removing all others from every node.
+    ///
+    /// # Examples
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use spatialize::quadtree::{Aabb, Bounded, Quadtree};
+    /// let mut qt = Quadtree::new(-10.0, 10.0, 20.0, 20.0);
+    /// qt.insert(Rc::new(Aabb::new(0.0, -5.0, 5.0, 0.0))).unwrap();
+    /// qt.insert(Rc::new(Aabb::new(-8.0, 8.0, -8.0, 8.0))).unwrap();
+    /// qt.insert(Rc::new(Aabb::new(-1.0, -1.0, 1.0, 1.0))).unwrap();
+    /// qt.insert(Rc::new(Aabb::new(3.0, -3.0, 7.0, -3.0))).unwrap();
+    ///
+    /// qt.retain(|rc| (rc.east_edge() - rc.west_edge()) * (rc.north_edge() - rc.south_edge()) > 0.0);
+    /// assert_eq!(2, qt.len());
+    /// ```
+    pub fn retain<F: FnMut(&Rc<dyn Bounded<T>>) -> bool>(&mut self, mut f: F) {
+        self.retain_with(&mut f);
+    }
+
+    /// A private function doing the traversal of `retain`.
+    fn retain_with<F: FnMut(&Rc<dyn Bounded<T>>) -> bool>(&mut self, f: &mut F) {
+        self.contents
+            .retain(|entry| entry.get().is_some_and(|rc| f(&rc)));
+        for rc_ref in self.quadrants() {
+            rc_ref.borrow_mut().retain_with(f);
+        }
+    }
+
+    /// Removes every object overlapping `rect` and returns them. Only quadrants whose bounds
+    /// overlap `rect` are visited, and quadrants left empty are kept until `prune` is called.
+    ///
+    /// # Examples
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use spatialize::quadtree::{Aabb, Bounded, Quadtree};
+    /// let mut qt = Quadtree::with_capacity(-10.0, 10.0, 20.0, 20.0, 1);
+    /// qt.insert(Rc::new(Aabb::new(-8.0, 6.0, -6.0, 8.0))).unwrap();
+    /// qt.insert(Rc::new(Aabb::new(-4.0, 2.0, -2.0, 4.0))).unwrap();
+    /// qt.insert(Rc::new(Aabb::new(4.0, 2.0, 6.0, 4.0))).unwrap();
+    /// qt.insert(Rc::new(Aabb::new(4.0, -6.0, 6.0, -4.0))).unwrap();
+    ///
+    /// // everything in the northwest quadrant
+    /// let removed = qt.remove_in_rect(&Aabb::new(-9.0, 1.0, -1.0, 9.0));
+    /// assert_eq!(2, removed.len());
+    /// assert_eq!(2, qt.len());
+    /// qt.prune();
+    ///
+    /// let rect_view = Aabb::new(0.0, -10.0, 10.0, 10.0);
+    /// let mut result_vec: Vec<Rc<dyn Bounded>> = vec![];
+    /// qt.get_rect(&rect_view, &mut result_vec).unwrap();
+    /// assert_eq!(2, result_vec.len());
+    /// ```
+    pub fn remove_in_rect(&mut self, rect: &dyn Bounded<T>) -> Vec<Rc<dyn Bounded<T>>> {
+        let mut removed = vec![];
+        self.remove_in_rect_with(rect, &mut removed);
+        removed
+    }
+
+    /// A private function doing the traversal of `remove_in_rect`.
+    fn remove_in_rect_with(
+        &mut self,
+        rect: &dyn Bounded<T>,
+        removed: &mut Vec<Rc<dyn Bounded<T>>>,
+    ) {
+        if self.intersects(rect) {
+            self.contents.retain(|entry| match entry.get() {
+                Some(rc) if rc.intersects(rect) => {
+                    removed.push(rc);
+                    false
+                }
+                Some(_) => true,
+                None => false,
+            });
+            for rc_ref in self.quadrants() {
+                rc_ref.borrow_mut().remove_in_rect_with(rect, removed);
+            }
+        }
+    }
+
+    /// Merges the quadrants of every node whose subtree holds no more objects than the node
+    /// capacity back into that node, undoing subdivisions that are no longer needed after
+    /// objects were removed.
+    ///
+    /// # Examples
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use spatialize::quadtree::{Aabb, Bounded, Quadtree};
+    /// let mut qt = Quadtree::with_capacity(-10.0, 10.0, 20.0, 20.0, 1);
+    /// let sized_object: Rc<dyn Bounded> = Rc::new(Aabb::new(0.0, -5.0, 5.0, 0.0));
+    /// qt.insert(Rc::clone(&sized_object)).unwrap();
+    /// qt.insert(Rc::new(Aabb::new(-8.0, 6.0, -6.0, 8.0))).unwrap();
+    /// assert!(qt.is_divided());
+    ///
+    /// qt.remove(&sized_object).unwrap();
+    /// qt.prune();
+    /// assert!(!qt.is_divided());
+    /// assert_eq!(1, qt.len());
+    /// ```
+    pub fn prune(&mut self) {
+        self.contents.retain(|entry| entry.is_alive());
+        for rc_ref in self.quadrants() {
+            rc_ref.borrow_mut().prune();
+        }
+        if self.divided && self.len() <= self.capacity {
+            let mut contents = core::mem::take(&mut self.contents);
+            for rc_ref in self.quadrants() {
+                contents.append(&mut rc_ref.borrow_mut().contents);
+            }
+            self.contents = contents;
+            self.northeast_quad = None;
+            self.northwest_quad = None;
+            self.southeast_quad = None;
+            self.southwest_quad = None;
+            self.divided = false;
+        }
+    }
+
+    /// Rebuilds the `Quadtree` in place, taking every object out of its nodes and inserting it
+    /// again into the root with the same bounds, capacity and maximum depth. This reshapes a
+    /// tree left lopsided by removals or by objects whose edges changed since they were
+    /// inserted. Objects now reaching past the bounds are kept in the root like with
+    /// `insert_clamped`, and objects no longer overlapping the bounds at all are removed.
+    ///
+    /// # Examples
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use spatialize::quadtree::{Aabb, Bounded, Point, Quadtree};
+    /// let mut qt = Quadtree::with_capacity(-10.0, 10.0, 20.0, 20.0, 2);
+    /// let mut clustered = vec![];
+    /// for i in 1..4 {
+    ///     let offset = i as f32 * 0.1;
+    ///     let sized_object: Rc<dyn Bounded> = Rc::new(Point::new(-10.0 + offset, 10.0 - offset));
+    ///     qt.insert(Rc::clone(&sized_object)).unwrap();
+    ///     clustered.push(sized_object);
+    /// }
+    /// qt.insert(Rc::new(Aabb::new(5.0, 4.0, 6.0, 5.0))).unwrap();
+    /// qt.remove(&clustered[0]).unwrap();
+    /// qt.remove(&clustered[1]).unwrap();
+    ///
+    /// let view = Aabb::new(-10.0, -10.0, 10.0, 10.0);
+    /// let mut before: Vec<Rc<dyn Bounded>> = vec![];
+    /// qt.get_rect(&view, &mut before).unwrap();
+    /// let depth = qt.depth();
+    /// qt.rebuild();
+    /// let mut after: Vec<Rc<dyn Bounded>> = vec![];
+    /// qt.get_rect(&view, &mut after).unwrap();
+    ///
+    /// assert!(qt.depth() < depth);
+    /// assert_eq!(before.len(), after.len());
+    /// assert!(before.iter().all(|rc| after.iter().any(|other| Rc::ptr_eq(rc, other))));
+    /// ```
+    pub fn rebuild(&mut self) {
+        let mut entries = Contents::new();
+        self.take_entries(&mut entries);
+        let ids = core::mem::take(&mut self.ids);
+        self.clear();
+        self.ids = ids;
+        for entry in entries {
+            if let Some(sized_object) = entry.get() {
+                let _ = self.insert_entry_clamped(&sized_object, entry);
+            }
+        }
+    }
+
+    /// Moves every object of `other` into this `Quadtree`, inserting them one by one. Objects
+    /// keep their layer masks, and objects `other` only holds weakly stay weak. Duplicates
+    /// rejected by a `Quadtree` built with `QuadtreeBuilder::dedup` are skipped, and ids
+    /// issued by `other` aren't valid in this `Quadtree`.
+    ///
+    /// If the bounds of `other` don't lie within the bounds of this `Quadtree`, nothing is
+    /// inserted and `QuadtreeError::OutOfBounds` is returned.
+    ///
+    /// # Examples
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use spatialize::quadtree::{Aabb, Bounded, Quadtree};
+    /// use spatialize::quadtree::QuadtreeError;
+    ///
+    /// let mut world = Quadtree::new(-10.0, 10.0, 20.0, 20.0);
+    /// world.insert(Rc::new(Aabb::new(-8.0, 6.0, -6.0, 8.0))).unwrap();
+    ///
+    /// let mut local = Quadtree::new(0.0, 5.0, 5.0, 5.0);
+    /// local.insert(Rc::new(Aabb::new(1.0, 3.0, 2.0, 4.0))).unwrap();
+    /// local.insert(Rc::new(Aabb::new(3.0, 1.0, 4.0, 2.0))).unwrap();
+    /// world.absorb(local).unwrap();
+    /// assert_eq!(3, world.len());
+    ///
+    /// let rect_view = Aabb::new(0.0, 0.0, 5.0, 5.0);
+    /// let mut result_vec: Vec<Rc<dyn Bounded>> = vec![];
+    /// world.get_rect(&rect_view, &mut result_vec).unwrap();
+    /// assert_eq!(2, result_vec.len());
+    ///
+    /// let outside = Quadtree::new(5.0, 5.0, 10.0, 10.0);
+    /// assert!(matches!(
+    ///     world.absorb(outside),
+    ///     Err(QuadtreeError::OutOfBounds { .. })
+    /// ));
+    /// ```
+    pub fn absorb(&mut self, mut other: Self) -> Result<(), QuadtreeError> {
+        let (position_x, position_y, width, height) = other.bounds();
+        let bounds = Edges {
+            north: position_y,
+            east: position_x + width,
+            south: position_y - height,
+            west: position_x,
+        };
+        if !self.fits(&bounds) {
+            return Err(out_of_bounds(&bounds, &self.loose_edges()));
+        }
+        let mut entries = Contents::new();
+        other.take_entries(&mut entries);
+        for entry in entries {
+            if let Some(sized_object) = entry.get() {
+                let _ = self.insert_entry(&sized_object, entry);
+            }
+        }
+        Ok(())
+    }
+
+    /// A private function moving the entries of this node and its quadrants into `entries`.
+    fn take_entries(&mut self, entries: &mut Contents<T>) {
+        entries.append(&mut self.contents);
+        for rc_ref in self.quadrants() {
+            rc_ref.borrow_mut().take_entries(entries);
+        }
+    }
+
+    /// Reserves capacity for at least `additional` more objects in the contents of this node,
+    /// avoiding reallocations when many objects are known to end up in it, e.g. objects
+    /// spanning the center of the `Quadtree`. Only this node is affected, not its quadrants.
+    ///
+    /// # Examples
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use spatialize::quadtree::{Aabb, Bounded, Quadtree};
+    /// let mut qt = Quadtree::new(-10.0, 10.0, 20.0, 20.0);
+    /// qt.reserve(100);
+    /// for _ in 0..100 {
+    ///     qt.insert(Rc::new(Aabb::new(-1.0, -1.0, 1.0, 1.0))).unwrap();
+    /// }
+    /// assert_eq!(100, qt.len());
+    /// ```
+    pub fn reserve(&mut self, additional: usize) {
+        self.contents.reserve(additional);
+    }
+
+    /// Shrinks the capacity of the contents of every node as much as possible, releasing the
+    /// memory kept after many objects were removed. Use `prune` first to also release the
+    /// quadrants that became empty.
+    ///
+    /// # Examples
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use spatialize::quadtree::{Aabb, Bounded, Quadtree};
+    /// let mut qt = Quadtree::new(-10.0, 10.0, 20.0, 20.0);
+    /// for _ in 0..100 {
+    ///     qt.insert(Rc::new(Aabb::new(-1.0, -1.0, 1.0, 1.0))).unwrap();
+    /// }
+    /// let mut kept = 0;
+    /// qt.retain(|_| {
+    ///     kept += 1;
+    ///     kept <= 3
+    /// });
+    /// qt.prune();
+    /// qt.shrink_to_fit();
+    /// assert_eq!(3, qt.len());
+    /// ```
+    pub fn shrink_to_fit(&mut self) {
+        self.contents.shrink_to_fit();
+        for rc_ref in self.quadrants() {
+            rc_ref.borrow_mut().shrink_to_fit();
+        }
+    }
+
+    /// Removes every object and quadrant, keeping the bounds, capacity and maximum depth.
+    ///
+    /// # Examples
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use spatialize::quadtree::{Aabb, Bounded, Quadtree};
+    /// let mut qt = Quadtree::new(-10.0, 10.0, 20.0, 20.0);
+    /// qt.insert(Rc::new(Aabb::new(0.0, -5.0, 5.0, 0.0))).unwrap();
+    /// qt.insert(Rc::new(Aabb::new(-8.0, 6.0, -6.0, 8.0))).unwrap();
+    /// qt.insert(Rc::new(Aabb::new(-1.0, -1.0, 1.0, 1.0))).unwrap();
+    /// qt.clear();
+    ///
+    /// let rect_view = Aabb::new(-10.0, -10.0, 10.0, 10.0);
+    /// let mut result_vec: Vec<Rc<dyn Bounded>> = vec![];
+    /// qt.get_rect(&rect_view, &mut result_vec).unwrap();
+    /// assert!(result_vec.is_empty());
+    /// ```
+    pub fn clear(&mut self) {
+        self.contents.clear();
+        self.ids.clear();
+        self.northeast_quad = None;
+        self.northwest_quad = None;
+        self.southeast_quad = None;
+        self.southwest_quad = None;
+        self.divided = false;
+    }
+
+    /// Returns the number of objects stored across all nodes of the `Quadtree`.
+    ///
+    /// # Examples
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use spatialize::quadtree::{Aabb, Bounded, Quadtree};
+    /// let mut qt = Quadtree::new(-10.0, 10.0, 20.0, 20.0);
+    /// qt.insert(Rc::new(Aabb::new(0.0, -5.0, 5.0, 0.0))).unwrap();
+    /// qt.insert(Rc::new(Aabb::new(-1.0, -1.0, 1.0, 1.0))).unwrap();
+    /// assert_eq!(2, qt.len());
+    /// ```
+    pub fn len(&self) -> usize {
+        self.node_len()
+            + self
+                .quadrants()
+                .map(|rc_ref| rc_ref.borrow().len())
+                .sum::<usize>()
+    }
+
+    /// Returns an estimate of the number of stored objects, counting the objects of every node
+    /// down to `sample_depth` below this node exactly and extrapolating the deeper nodes by
+    /// their count.
+    ///
+    /// The deeper nodes are only counted, without looking at their contents, and each leaf
+    /// among them is assumed to be half full, holding `capacity / 2` objects, or `1 / 2` for a
+    /// `capacity` of `0`. Skipping the contents of the deeper nodes saves the work of checking
+    /// every entry, but every node is still visited.
+    ///
+    /// The estimate is approximate, but monotonic with the number of stored objects: inserting
+    /// an object never lowers it, and removing one never raises it. A node subdividing at
+    /// `sample_depth` moves at most `capacity + 1` objects into four half full leaves, so the
+    /// estimate doesn't drop when objects move out of the sample. It equals `len` once
+    /// `sample_depth` reaches the `depth` of the tree. It's close to `len` when the objects are
+    /// spread evenly, but as removals don't merge nodes, the deeper leaves of a tree that lost
+    /// many objects are still counted as half full until it's rebuilt.
+    ///
+    /// # Examples
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use spatialize::quadtree::{Bounded, Point, Quadtree};
+    /// let mut qt = Quadtree::with_capacity(-10.0, 10.0, 20.0, 20.0, 4);
+    /// let mut estimates = vec![];
+    /// for i in 0..400 {
+    ///     let x = (i % 20) as f32 - 9.5;
+    ///     let y = (i / 20) as f32 - 9.5;
+    ///     qt.insert(Rc::new(Point::new(x, y))).unwrap();
+    ///     estimates.push(qt.estimate_len(1));
+    /// }
+    ///
+    /// let estimate = qt.estimate_len(1) as f32;
+    /// assert!((estimate - 400.0).abs() < 40.0);
+    /// assert!(estimates.windows(2).all(|pair| pair[0] <= pair[1]));
+    /// assert_eq!(qt.len(), qt.estimate_len(qt.depth()));
+    /// ```
+    pub fn estimate_len(&self, sample_depth: usize) -> usize {
+        let mut sample = LenSample::default();
+        self.sample_len(sample_depth, &mut sample);
+        // half of `capacity` per leaf, rounded down over all of them
+        sample.objects + sample.unsampled_leaves * self.capacity.max(1) / 2
+    }
+
+    /// A private function counting the objects of the nodes down to `sample_depth` below this
+    /// node and the number of deeper leaves into `sample`.
+    fn sample_len(&self, sample_depth: usize, sample: &mut LenSample) {
+        sample.objects += self.node_len();
+        for rc_ref in self.quadrants() {
+            if sample_depth == 0 {
+                rc_ref.borrow().count_leaves(sample);
+            } else {
+                rc_ref.borrow().sample_len(sample_depth - 1, sample);
+            }
+        }
+    }
+
+    /// A private function counting the leaves of this node into `sample`, without looking at
+    /// their contents.
+    fn count_leaves(&self, sample: &mut LenSample) {
+        if !self.divided {
+            sample.unsampled_leaves += 1;
+        }
+        for rc_ref in self.quadrants() {
+            rc_ref.borrow().count_leaves(sample);
+        }
+    }
+
+    /// Returns `true` if no objects are stored in the `Quadtree`.
+    ///
+    /// # Examples
+    /// ```
+    /// # use spatialize::quadtree::Quadtree;
+    /// let qt = Quadtree::new(-10.0, 10.0, 20.0, 20.0);
+    /// assert!(qt.is_empty());
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        !self.contents.iter().any(Entry::is_alive)
+            && self.quadrants().all(|rc_ref| rc_ref.borrow().is_empty())
+    }
+
+    /// Returns the maximum depth of any leaf below this node, which is at depth `0`.
+    ///
+    /// # Examples
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use spatialize::quadtree::{Aabb, Bounded, Quadtree};
+    /// let mut qt = Quadtree::with_limits(-10.0, 10.0, 20.0, 20.0, 0, 3);
+    /// assert_eq!(0, qt.depth());
+    /// qt.insert(Rc::new(Aabb::new(1.0, 1.0, 1.0, 1.0))).unwrap();
+    /// assert_eq!(3, qt.depth());
+    /// ```
+    pub fn depth(&self) -> usize {
+        self.quadrants()
+            .map(|rc_ref| rc_ref.borrow().depth() + 1)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Returns statistics about the shape of the `Quadtree` below this node.
+    ///
+    /// # Examples
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use spatialize::quadtree::{Aabb, Bounded, Quadtree};
+    /// use spatialize::quadtree::TreeStats;
+    ///
+    /// let mut qt = Quadtree::with_limits(-10.0, 10.0, 20.0, 20.0, 0, 2);
+    /// qt.insert(Rc::new(Aabb::new(1.0, 1.0, 1.0, 1.0))).unwrap();
+    /// qt.insert(Rc::new(Aabb::new(-1.0, -1.0, 1.0, 1.0))).unwrap();
+    ///
+    /// let stats = TreeStats {
+    ///     node_count: 9,
+    ///     leaf_count: 7,
+    ///     max_depth: 2,
+    ///     total_objects: 2,
+    /// };
+    /// assert_eq!(stats, qt.stats());
+    /// ```
+    pub fn stats(&self) -> TreeStats {
+        let mut stats = TreeStats::default();
+        self.visit_nodes(|node, depth| {
+            stats.node_count += 1;
+            stats.total_objects += node.node_len();
+            stats.max_depth = stats.max_depth.max(depth);
+            if !node.is_divided() {
+                stats.leaf_count += 1;
+            }
+        });
+        stats
+    }
+
+    /// Returns the fraction of leaves holding at least one object, from `0` for an empty
+    /// `Quadtree` to `1` if no leaf is empty. A low fill ratio in a deep tree indicates that
+    /// objects were removed or moved since the nodes were created, and `rebuild` would help.
+    ///
+    /// # Examples
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use spatialize::quadtree::{Aabb, Bounded, Quadtree};
+    /// let mut qt = Quadtree::with_capacity(-10.0, 10.0, 20.0, 20.0, 1);
+    /// assert_eq!(0.0, qt.fill_ratio());
+    ///
+    /// qt.insert(Rc::new(Aabb::new(1.0, 8.0, 2.0, 9.0))).unwrap();
+    /// qt.insert(Rc::new(Aabb::new(-9.0, 8.0, -8.0, 9.0))).unwrap();
+    /// qt.insert(Rc::new(Aabb::new(-9.0, -2.0, -8.0, -1.0))).unwrap();
+    /// assert_eq!(0.75, qt.fill_ratio());
+    /// assert_eq!(0.75, qt.avg_objects_per_leaf());
+    /// ```
+    pub fn fill_ratio(&self) -> T {
+        let (leaves, occupied, _) = self.occupancy();
+        ratio(occupied, leaves)
+    }
+
+    /// Returns the average number of objects held by the leaves, not counting objects stored
+    /// in divided nodes because they straddle quadrants.
+    pub fn avg_objects_per_leaf(&self) -> T {
+        let (leaves, _, objects) = self.occupancy();
+        ratio(objects, leaves)
+    }
+
+    /// A private function returning the number of leaves, the number of leaves holding
+    /// objects, and the number of objects held by leaves.
+    fn occupancy(&self) -> (usize, usize, usize) {
+        let mut occupancy = (0, 0, 0);
+        self.visit_nodes(|node, _| {
+            if !node.is_divided() {
+                let len = node.node_len();
+                occupancy.0 += 1;
+                occupancy.1 += usize::from(len > 0);
+                occupancy.2 += len;
+            }
+        });
+        occupancy
+    }
+
+    /// Calls `f` with every node and its depth below this node, which is at depth `0`, visiting
+    /// each node before its quadrants in the order northeast, northwest, southeast, southwest.
+    ///
+    /// # Examples
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use spatialize::quadtree::{Aabb, Bounded, Quadtree};
+    /// let mut qt = Quadtree::with_limits(-10.0, 10.0, 20.0, 20.0, 1, 4);
+    /// qt.insert(Rc::new(Aabb::new(-9.0, 8.0, -8.0, 9.0))).unwrap();
+    /// qt.insert(Rc::new(Aabb::new(-7.0, 6.0, -6.0, 7.0))).unwrap();
+    /// qt.insert(Rc::new(Aabb::new(-1.0, -1.0, 1.0, 1.0))).unwrap();
+    ///
+    /// let mut objects_per_depth = vec![0; 5];
+    /// qt.visit_nodes(|node, depth| objects_per_depth[depth] += node.node_len());
+    /// assert_eq!(vec![1, 0, 0, 2, 0], objects_per_depth);
+    /// ```
+    pub fn visit_nodes<F: FnMut(&Self, usize)>(&self, mut f: F) {
+        self.visit_nodes_with(0, &mut f);
+    }
+
+    /// A private function doing the traversal of `visit_nodes`.
+    fn visit_nodes_with<F: FnMut(&Self, usize)>(&self, depth: usize, f: &mut F) {
+        f(self, depth);
+        for rc_ref in self.quadrants() {
+            rc_ref.borrow().visit_nodes_with(depth + 1, f);
+        }
+    }
+
+    /// Calls `f` with every node and its depth below this node like `visit_nodes`, but in level
+    /// order: all nodes at one depth are visited before any deeper node, with the quadrants of
+    /// each node in the order northeast, northwest, southeast, southwest.
+    ///
+    /// # Examples
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use spatialize::quadtree::{Aabb, Bounded, Quadtree};
+    /// let mut qt = Quadtree::with_limits(-10.0, 10.0, 20.0, 20.0, 1, 4);
+    /// qt.insert(Rc::new(Aabb::new(-9.0, 8.0, -8.0, 9.0))).unwrap();
+    /// qt.insert(Rc::new(Aabb::new(-7.0, 6.0, -6.0, 7.0))).unwrap();
+    ///
+    /// let mut levels: Vec<Vec<(f32, f32, f32, f32)>> = vec![];
+    /// qt.visit_levels(|node, depth| {
+    ///     if levels.len() == depth {
+    ///         levels.push(vec![]);
+    ///     }
+    ///     levels[depth].push(node.bounds());
+    /// });
+    /// assert_eq!(vec![1, 4, 4, 4], levels.iter().map(Vec::len).collect::<Vec<_>>());
+    /// assert_eq!((-10.0, 10.0, 5.0, 5.0), levels[2][1]);
+    /// ```
+    pub fn visit_levels<F: FnMut(&Self, usize)>(&self, mut f: F) {
+        f(self, 0);
+        let mut pending: VecDeque<_> = self
+            .quadrants()
+            .map(|rc_ref| (Rc::clone(rc_ref), 1))
+            .collect();
+        while let Some((rc_ref, depth)) = pending.pop_front() {
+            let node = rc_ref.borrow();
+            f(&node, depth);
+            pending.extend(
+                node.quadrants()
+                    .map(|rc_ref| (Rc::clone(rc_ref), depth + 1)),
+            );
+        }
+    }
+
+    /// Pushes the bounds of every undivided node, the terminal cells of the partition, into
+    /// `out` as `(position_x, position_y, width, height)` like `bounds`. Together the cells
+    /// cover the bounds of the `Quadtree` without overlapping.
+    ///
+    /// # Examples
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use spatialize::quadtree::{Aabb, Bounded, Quadtree};
+    /// let mut qt = Quadtree::with_limits(-10.0, 10.0, 20.0, 20.0, 1, 4);
+    /// qt.insert(Rc::new(Aabb::new(-9.0, 8.0, -8.0, 9.0))).unwrap();
+    /// qt.insert(Rc::new(Aabb::new(5.0, -6.0, 6.0, -5.0))).unwrap();
+    ///
+    /// let mut cells = vec![];
+    /// qt.leaf_bounds(&mut cells);
+    /// assert_eq!(4, cells.len());
+    /// assert!(cells.contains(&(-10.0, 10.0, 10.0, 10.0)));
+    /// assert!(cells.contains(&(0.0, 0.0, 10.0, 10.0)));
+    /// ```
+    pub fn leaf_bounds(&self, out: &mut Vec<(T, T, T, T)>) {
+        self.visit_nodes(|node, _| {
+            if !node.is_divided() {
+                out.push(node.bounds());
+            }
+        });
+    }
+
+    /// Pushes the bounds of every undivided node overlapping `rect` into `out` as
+    /// `(position_x, position_y, width, height)` like `leaf_bounds`, e.g. to find the chunks of
+    /// a world to load for a region. Only the structure of the `Quadtree` is looked at, not the
+    /// stored objects, and cells are compared without the expansion of a loose `Quadtree`.
+    ///
+    /// # Examples
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use spatialize::quadtree::{Aabb, Bounded, Quadtree};
+    /// let qt: Quadtree = Quadtree::grid((-10.0, 10.0, 20.0, 20.0), 2);
+    ///
+    /// let mut cells = vec![];
+    /// qt.leaves_in_rect(&Aabb::new(-9.0, 6.0, -6.0, 9.0), &mut cells);
+    /// assert_eq!(vec![(-10.0, 10.0, 5.0, 5.0)], cells);
+    ///
+    /// cells.clear();
+    /// qt.leaves_in_rect(&Aabb::new(-1.0, -1.0, 1.0, 1.0), &mut cells);
+    /// assert_eq!(4, cells.len());
+    /// assert!(cells.contains(&(0.0, 0.0, 5.0, 5.0)));
+    /// ```
+    pub fn leaves_in_rect(&self, rect: &dyn Bounded<T>, out: &mut Vec<(T, T, T, T)>) {
+        let edges = Edges {
+            north: self.position_y,
+            east: self.position_x + self.width,
+            south: self.position_y - self.height,
+            west: self.position_x,
+        };
+        if !rect.intersects(&edges) {
+            return;
+        }
+        if self.divided {
+            for rc_ref in self.quadrants() {
+                rc_ref.borrow().leaves_in_rect(rect, out);
+            }
+        } else {
+            out.push(self.bounds());
+        }
+    }
+
+    /// Returns the edges of every stored object as `(north, east, south, west)`, in the order
+    /// `iter` returns the objects, e.g. for writing them to a CSV file.
+    ///
+    /// # Examples
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use spatialize::quadtree::{Aabb, Bounded, Quadtree};
+    /// let mut qt = Quadtree::new(-10.0, 10.0, 20.0, 20.0);
+    /// qt.insert(Rc::new(Aabb::new(-1.0, -1.0, 1.0, 1.0))).unwrap();
+    /// qt.insert(Rc::new(Aabb::new(2.0, 3.0, 3.0, 4.0))).unwrap();
+    ///
+    /// let records = qt.to_records();
+    /// assert_eq!(2, records.len());
+    /// assert!(records.contains(&(1.0, 1.0, -1.0, -1.0)));
+    /// assert!(records.contains(&(4.0, 3.0, 3.0, 2.0)));
+    /// ```
+    pub fn to_records(&self) -> Vec<(T, T, T, T)> {
+        self.iter()
+            .map(|rc| {
+                (
+                    rc.north_edge(),
+                    rc.east_edge(),
+                    rc.south_edge(),
+                    rc.west_edge(),
+                )
+            })
+            .collect()
+    }
+
+    /// Returns the edges of every stored object as `[west, south, east, north]`, the minimum
+    /// and then the maximum corner, converted to `f32` and in the order `iter` returns the
+    /// objects. The flat, `Send` array can be uploaded to the GPU as is, e.g. as a buffer of
+    /// `vec4<f32>` instances.
+    ///
+    /// # Examples
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use spatialize::quadtree::{Aabb, Bounded, Quadtree};
+    /// let mut qt = Quadtree::new(-10.0, 10.0, 20.0, 20.0);
+    /// qt.insert(Rc::new(Aabb::new(-1.0, -1.0, 1.0, 1.0))).unwrap();
+    /// qt.insert(Rc::new(Aabb::new(2.0, 3.0, 3.0, 4.0))).unwrap();
+    ///
+    /// let snapshot = qt.snapshot_bounds();
+    /// let uploaded = std::thread::spawn(move || snapshot).join().unwrap();
+    /// assert_eq!(2, uploaded.len());
+    /// assert!(uploaded.contains(&[-1.0, -1.0, 1.0, 1.0]));
+    /// assert!(uploaded.contains(&[2.0, 3.0, 3.0, 4.0]));
+    /// ```
+    pub fn snapshot_bounds(&self) -> Vec<[f32; 4]> {
+        let number = |value: T| value.to_f32().unwrap_or(f32::NAN);
+        self.iter()
+            .map(|rc| {
+                [
+                    number(rc.west_edge()),
+                    number(rc.south_edge()),
+                    number(rc.east_edge()),
+                    number(rc.north_edge()),
+                ]
+            })
+            .collect()
+    }
+
+    /// Returns the bounds of every node as `(position_x, position_y, width, height, depth)`,
+    /// in the order `visit_nodes` visits them, e.g. for plotting the partition.
+    ///
+    /// # Examples
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use spatialize::quadtree::{Aabb, Bounded, Quadtree};
+    /// let mut qt = Quadtree::with_limits(-10.0, 10.0, 20.0, 20.0, 0, 1);
+    /// qt.insert(Rc::new(Aabb::new(2.0, 3.0, 3.0, 4.0))).unwrap();
+    ///
+    /// let records = qt.node_records();
+    /// assert_eq!(5, records.len());
+    /// assert_eq!((-10.0, 10.0, 20.0, 20.0, 0), records[0]);
+    /// assert_eq!((0.0, 10.0, 10.0, 10.0, 1), records[1]);
+    /// ```
+    pub fn node_records(&self) -> Vec<(T, T, T, T, usize)> {
+        let mut records = vec![];
+        self.visit_nodes(|node, depth| {
+            let (position_x, position_y, width, height) = node.bounds();
+            records.push((position_x, position_y, width, height, depth));
+        });
+        records
+    }
+
+    /// Returns the number of objects whose center falls into each cell of a uniform grid of
+    /// `cols` columns and `rows` rows spanning the bounds of the `Quadtree`, indexed as
+    /// `grid[row][col]` with row `0` at the north edge and column `0` at the west edge. Centers
+    /// on the boundary between two cells count towards the cell to the south or east of it.
+    ///
+    /// # Examples
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use spatialize::quadtree::{Aabb, Bounded, Quadtree};
+    /// let mut qt = Quadtree::new(-10.0, 10.0, 20.0, 20.0);
+    /// qt.insert(Rc::new(Aabb::new(-9.0, 8.0, -8.0, 9.0))).unwrap();
+    /// qt.insert(Rc::new(Aabb::new(-8.0, 7.0, -7.0, 8.0))).unwrap();
+    /// qt.insert(Rc::new(Aabb::new(5.0, -6.0, 6.0, -5.0))).unwrap();
+    /// qt.insert(Rc::new(Aabb::new(-1.0, -1.0, 1.0, 1.0))).unwrap();
+    ///
+    /// assert_eq!(vec![vec![2, 0], vec![0, 2]], qt.density_grid(2, 2));
+    /// ```
+    pub fn density_grid(&self, cols: usize, rows: usize) -> Vec<Vec<usize>> {
+        let mut grid = vec![vec![0; cols]; rows];
+        if cols == 0 || rows == 0 {
+            return grid;
+        }
+        let cell = |offset: T, length: T, count: usize| {
+            let scaled = offset / length * T::from(count).unwrap_or_else(T::one);
+            scaled.floor().to_usize().unwrap_or(0).min(count - 1)
+        };
+        for sized_object in self.iter() {
+            let (x, y) = sized_object.center();
+            let col = cell(x - self.position_x, self.width, cols);
+            let row = cell(self.position_y - y, self.height, rows);
+            grid[row][col] += 1;
+        }
+        grid
+    }
+
+    /// Returns the number of objects stored in this node itself, not counting its quadrants.
+    ///
+    /// # Examples
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use spatialize::quadtree::{Aabb, Bounded, Quadtree};
+    /// let mut qt = Quadtree::with_capacity(-10.0, 10.0, 20.0, 20.0, 1);
+    /// qt.insert(Rc::new(Aabb::new(-1.0, -1.0, 1.0, 1.0))).unwrap();
+    /// qt.insert(Rc::new(Aabb::new(2.0, 1.0, 3.0, 2.0))).unwrap();
+    /// assert_eq!(1, qt.node_len());
+    /// assert_eq!(2, qt.len());
+    /// ```
+    pub fn node_len(&self) -> usize {
+        self.contents
+            .iter()
+            .filter(|entry| entry.is_alive())
+            .count()
+    }
+
+    /// Returns an iterator over the objects stored in this node itself, not in its quadrants.
+    /// These are the objects that don't fit in any quadrant, e.g. because they straddle the
+    /// center, or all objects of a leaf. Together with the quadrant getters like `northeast`
+    /// this allows walking the exact structure of the `Quadtree`.
+    ///
+    /// This isn't a borrowed slice of the contents: a node stores both strong and weak
+    /// references, so each object is yielded as a cloned `Rc`, raising its reference count
+    /// while it's held, and objects inserted with `insert_weak` are upgraded on access.
+    /// Weakly referenced objects that were dropped are omitted, so the count matches
+    /// `node_len`. To inspect objects without cloning, use `for_each_in_rect` instead.
+    ///
+    /// # Examples
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use spatialize::quadtree::{Aabb, Bounded, Quadtree};
+    /// let mut qt = Quadtree::new(-10.0, 10.0, 20.0, 20.0);
+    /// let straddling: Rc<dyn Bounded> = Rc::new(Aabb::new(-1.0, -1.0, 1.0, 1.0));
+    /// qt.insert(Rc::clone(&straddling)).unwrap();
+    /// qt.insert(Rc::new(Aabb::new(2.0, 1.0, 3.0, 2.0))).unwrap();
+    ///
+    /// let at_root: Vec<_> = qt.node_contents().collect();
+    /// assert_eq!(1, at_root.len());
+    /// assert!(Rc::ptr_eq(&straddling, &at_root[0]));
+    /// assert_eq!(0, qt.southwest().unwrap().borrow().node_contents().count());
+    /// ```
+    pub fn node_contents(&self) -> impl Iterator<Item = Rc<dyn Bounded<T>>> + '_ {
+        self.contents.iter().filter_map(Entry::get)
+    }
+
+    /// Returns an iterator over every object stored in the `Quadtree`, depth-first.
+    ///
+    /// # Examples
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use spatialize::quadtree::{Aabb, Bounded, Quadtree};
+    /// let mut qt = Quadtree::new(-10.0, 10.0, 20.0, 20.0);
+    /// for i in 0..10 {
+    ///     let offset = i as f32 * 2.0;
+    ///     let (x, y) = (-10.0 + offset, 10.0 - offset);
+    ///     qt.insert(Rc::new(Aabb::new(x, y - 1.0, x + 1.0, y))).unwrap();
+    /// }
+    /// assert_eq!(10, qt.iter().count());
+    /// ```
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            contents: self.contents.iter(),
+            pending: self.quadrants().rev().cloned().collect(),
+            current: None,
+            rect: None,
+        }
+    }
+
+    /// Returns an iterator over every object overlapping `rect`, walking the `Quadtree` lazily
+    /// like `iter` and skipping quadrants that don't overlap `rect`.
+    ///
+    /// The iterator yields a cloned `Rc` of every matching object rather than a reference, as
+    /// all nodes but the root are behind a `RefCell` and can be modified through the quadrant
+    /// getters while iterating, so a reference into them can't outlive the borrow of its node.
+    /// Objects are tested against `rect` by reference, so only the objects yielded are cloned;
+    /// weakly referenced objects are upgraded to be tested, though. To inspect objects without
+    /// touching any reference counts, use `for_each_in_rect`, which passes each object by
+    /// reference while its node is borrowed.
+    ///
+    /// # Examples
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use spatialize::quadtree::{Aabb, Bounded, Quadtree};
+    /// let mut qt = Quadtree::new(-10.0, 10.0, 20.0, 20.0);
+    /// qt.insert(Rc::new(Aabb::new(0.0, -5.0, 5.0, 0.0))).unwrap();
+    /// qt.insert(Rc::new(Aabb::new(-8.0, 6.0, -6.0, 8.0))).unwrap();
+    /// qt.insert(Rc::new(Aabb::new(-1.0, -1.0, 1.0, 1.0))).unwrap();
+    ///
+    /// let rect_view = Aabb::new(-2.0, -8.0, 8.0, 2.0);
+    /// assert_eq!(2, qt.iter_rect(&rect_view).count());
+    /// let first_large = qt
+    ///     .iter_rect(&rect_view)
+    ///     .find(|rc| rc.east_edge() - rc.west_edge() > 4.0);
+    /// assert!(first_large.is_some());
+    /// ```
+    pub fn iter_rect<'a>(&'a self, rect: &'a dyn Bounded<T>) -> Iter<'a, T> {
+        if self.intersects(rect) {
+            Iter {
+                contents: self.contents.iter(),
+                pending: self.quadrants().rev().cloned().collect(),
+                current: None,
+                rect: Some(rect),
+            }
+        } else {
+            Iter {
+                contents: [].iter(),
+                pending: vec![],
+                current: None,
+                rect: Some(rect),
+            }
+        }
+    }
+
+    /// Searches the `Quadtree` using a two-dimensional view that implementing `Bounded`
+    ///
+    /// The view is only borrowed, so any value implementing `Bounded` can be passed by reference
+    /// without wrapping it in an `Rc`, and an `Rc<dyn Bounded>` can be passed as `&*rc`.
+    ///
+    /// Only objects whose edges intersect the view are pushed into `vec`, even when they share
+    /// a node with objects that do. A view outside the bounds of the `Quadtree` is a valid
+    /// query with no results, so `vec` is left untouched and `Ok` is returned.
+    ///
+    /// # Examples
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use spatialize::quadtree::{Aabb, Bounded, Quadtree};
+    /// let mut qt = Quadtree::new(-10.0, 10.0, 20.0, 20.0);
+    /// let sized_object: Rc<dyn Bounded> = Rc::new(Aabb::new(0.0, -5.0, 5.0, 0.0));
+    /// match qt.insert(Rc::clone(&sized_object)) {
+    ///     Ok(_) => {
+    ///         let rect_view = Aabb::new(-2.0, -8.0, 8.0, 2.0);
+    ///         let mut result_vec: Vec<Rc<dyn Bounded>> = vec![];
+    ///         match qt.get_rect(&rect_view, &mut result_vec) {
+    ///             Ok(_) => assert_eq!(1, result_vec.len()),
+    ///             Err(e) => eprintln!("{}", e),
+    ///         }
+    ///     },
+    ///     Err(e) => eprintln!("{}", e),
+    /// }
+    /// ```
+    ///
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use spatialize::quadtree::{Aabb, Bounded, Quadtree};
+    /// let mut qt = Quadtree::with_capacity(-10.0, 10.0, 20.0, 20.0, 4);
+    /// qt.insert(Rc::new(Aabb::new(-9.0, 7.0, -7.0, 9.0))).unwrap();
+    /// qt.insert(Rc::new(Aabb::new(7.0, -9.0, 9.0, -7.0))).unwrap();
+    ///
+    /// let rect_view = Aabb::new(-10.0, 5.0, -5.0, 10.0);
+    /// let mut result_vec: Vec<Rc<dyn Bounded>> = vec![];
+    /// qt.get_rect(&rect_view, &mut result_vec).unwrap();
+    /// assert_eq!(1, result_vec.len());
+    ///
+    /// let off_screen = Aabb::new(50.0, 45.0, 55.0, 50.0);
+    /// let mut result_vec: Vec<Rc<dyn Bounded>> = vec![];
+    /// assert_eq!(Ok(()), qt.get_rect(&off_screen, &mut result_vec));
+    /// assert!(result_vec.is_empty());
+    /// ```
+    pub fn get_rect(
+        &self,
+        rect: &dyn Bounded<T>,
+        vec: &mut Vec<Rc<dyn Bounded<T>>>,
+    ) -> Result<(), QuadtreeError> {
+        self.collect_overlapping(rect, vec)
+    }
+
+    /// Pushes every object overlapping `rect` into `out` like `get_rect`, sorted by their west
+    /// edge, then south, east and north edge. The order doesn't depend on how the objects were
+    /// inserted or placed, which makes the output reproducible, at the cost of sorting it.
+    ///
+    /// # Examples
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use spatialize::quadtree::{Aabb, Bounded, Quadtree};
+    /// let mut qt = Quadtree::with_capacity(-10.0, 10.0, 20.0, 20.0, 1);
+    /// qt.insert(Rc::new(Aabb::new(4.0, 3.0, 5.0, 4.0))).unwrap();
+    /// qt.insert(Rc::new(Aabb::new(-6.0, -3.0, -5.0, -2.0))).unwrap();
+    /// qt.insert(Rc::new(Aabb::new(-6.0, 5.0, -5.0, 6.0))).unwrap();
+    ///
+    /// let rect_view = Aabb::new(-10.0, -10.0, 10.0, 10.0);
+    /// let mut result_vec: Vec<Rc<dyn Bounded>> = vec![];
+    /// qt.get_rect_sorted(&rect_view, &mut result_vec);
+    /// let corners: Vec<_> = result_vec
+    ///     .iter()
+    ///     .map(|rc| (rc.west_edge(), rc.south_edge()))
+    ///     .collect();
+    /// assert_eq!(vec![(-6.0, -3.0), (-6.0, 5.0), (4.0, 3.0)], corners);
+    /// ```
+    pub fn get_rect_sorted(&self, rect: &dyn Bounded<T>, out: &mut Vec<Rc<dyn Bounded<T>>>) {
+        let start = out.len();
+        self.for_each_in_rect(rect, |rc| out.push(Rc::clone(rc)));
+        out[start..].sort_by(|a, b| {
+            let edges = |rc: &Rc<dyn Bounded<T>>| {
+                [
+                    rc.west_edge(),
+                    rc.south_edge(),
+                    rc.east_edge(),
+                    rc.north_edge(),
+                ]
+            };
+            edges(a).partial_cmp(&edges(b)).unwrap_or(Ordering::Equal)
+        });
+    }
+
+    /// Pushes every object overlapping the box at `(position_x, position_y)` with the given
+    /// `width` and `height` into `out` like `get_rect`, without needing an `Rc` for the box.
+    /// The box is given like the bounds in `Quadtree::new`.
+    ///
+    /// # Examples
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use spatialize::quadtree::{Aabb, Bounded, Quadtree};
+    /// let mut qt = Quadtree::new(-10.0, 10.0, 20.0, 20.0);
+    /// qt.insert(Rc::new(Aabb::new(0.0, -5.0, 5.0, 0.0))).unwrap();
+    /// qt.insert(Rc::new(Aabb::new(-8.0, 6.0, -6.0, 8.0))).unwrap();
+    ///
+    /// let mut result_vec: Vec<Rc<dyn Bounded>> = vec![];
+    /// qt.query_bounds(-2.0, 2.0, 10.0, 10.0, &mut result_vec).unwrap();
+    /// assert_eq!(1, result_vec.len());
+    /// assert!(qt.query_bounds(20.0, 20.0, 1.0, 1.0, &mut result_vec).is_ok());
+    /// assert_eq!(1, result_vec.len());
+    /// ```
+    pub fn query_bounds(
+        &self,
+        position_x: T,
+        position_y: T,
+        width: T,
+        height: T,
+        out: &mut Vec<Rc<dyn Bounded<T>>>,
+    ) -> Result<(), QuadtreeError> {
+        let rect = Edges {
+            north: position_y,
+            east: position_x + width,
+            south: position_y - height,
+            west: position_x,
+        };
+        self.collect_overlapping(&rect, out)
+    }
+
+    /// Pushes every object overlapping the view of a camera centered on `(center_x, center_y)`
+    /// into `out` like `get_rect`. The view extends `half_width` and `half_height` from the
+    /// center, plus `margin` on every side, so objects just outside the screen are included.
+    ///
+    /// # Examples
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use spatialize::quadtree::{Aabb, Bounded, Quadtree};
+    /// let mut qt = Quadtree::new(-10.0, 10.0, 20.0, 20.0);
+    /// qt.insert(Rc::new(Aabb::new(-1.0, -1.0, 1.0, 1.0))).unwrap();
+    /// qt.insert(Rc::new(Aabb::new(5.0, -2.0, 6.0, -1.0))).unwrap();
+    ///
+    /// let mut on_screen: Vec<Rc<dyn Bounded>> = vec![];
+    /// qt.query_view(0.0, 0.0, 4.0, 3.0, 0.0, &mut on_screen);
+    /// assert_eq!(1, on_screen.len());
+    ///
+    /// let mut with_margin: Vec<Rc<dyn Bounded>> = vec![];
+    /// qt.query_view(0.0, 0.0, 4.0, 3.0, 1.5, &mut with_margin);
+    /// assert_eq!(2, with_margin.len());
+    /// ```
+    pub fn query_view(
+        &self,
+        center_x: T,
+        center_y: T,
+        half_width: T,
+        half_height: T,
+        margin: T,
+        out: &mut Vec<Rc<dyn Bounded<T>>>,
+    ) {
+        let view = Edges {
+            north: center_y + half_height + margin,
+            east: center_x + half_width + margin,
+            south: center_y - half_height - margin,
+            west: center_x - half_width - margin,
+        };
+        self.for_each_in_rect(&view, |rc| out.push(Rc::clone(rc)));
+    }
+
+    /// A private function doing the traversal of `get_rect` and `query_bounds`.
+    fn collect_overlapping(
+        &self,
+        rect: &dyn Bounded<T>,
+        out: &mut Vec<Rc<dyn Bounded<T>>>,
+    ) -> Result<(), QuadtreeError> {
+        self.for_each_in_rect(rect, |rc| out.push(Rc::clone(rc)));
+        Ok(())
+    }
+
+    /// Returns the number of objects overlapping `rect`, which matches the number of objects
+    /// `get_rect` would push, without collecting them.
+    ///
+    /// # Examples
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use spatialize::quadtree::{Aabb, Bounded, Quadtree};
+    /// let mut qt = Quadtree::new(-10.0, 10.0, 20.0, 20.0);
+    /// qt.insert(Rc::new(Aabb::new(0.0, -5.0, 5.0, 0.0))).unwrap();
+    /// qt.insert(Rc::new(Aabb::new(-8.0, 6.0, -6.0, 8.0))).unwrap();
+    /// qt.insert(Rc::new(Aabb::new(-1.0, -1.0, 1.0, 1.0))).unwrap();
+    ///
+    /// let rect_view = Aabb::new(-2.0, -8.0, 8.0, 2.0);
+    /// let mut result_vec: Vec<Rc<dyn Bounded>> = vec![];
+    /// qt.get_rect(&rect_view, &mut result_vec).unwrap();
+    /// assert_eq!(result_vec.len(), qt.count_in_rect(&rect_view));
+    /// assert_eq!(2, qt.count_in_rect(&rect_view));
+    /// ```
+    pub fn count_in_rect(&self, rect: &dyn Bounded<T>) -> usize {
+        let mut count = 0;
+        self.for_each_in_rect(rect, |_| count += 1);
+        count
+    }
+
+    /// Pushes every object sharing an edge with `rect` into `out`, without overlapping its
+    /// interior, e.g. to snap an object to its neighbors. An object touches `rect` if one of its
+    /// edges lies within `epsilon` of the opposite edge of `rect`, and the two overlap by more
+    /// than `epsilon` along that edge, so objects only meeting `rect` at a corner don't count.
+    ///
+    /// # Examples
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use spatialize::quadtree::{Aabb, Bounded, Quadtree};
+    /// let mut qt = Quadtree::new(-10.0, 10.0, 20.0, 20.0);
+    /// let flush: Rc<dyn Bounded> = Rc::new(Aabb::new(2.0001, 0.0, 5.0001, 1.0));
+    /// qt.insert(Rc::clone(&flush)).unwrap();
+    /// // overlapping, at a corner and a little apart
+    /// qt.insert(Rc::new(Aabb::new(1.0, 0.0, 3.0, 1.0))).unwrap();
+    /// qt.insert(Rc::new(Aabb::new(2.0, 2.0, 3.0, 3.0))).unwrap();
+    /// qt.insert(Rc::new(Aabb::new(-1.0, -3.5, 2.0, -2.5))).unwrap();
+    ///
+    /// let region = Aabb::new(0.0, -2.0, 2.0, 2.0);
+    /// let mut touching: Vec<Rc<dyn Bounded>> = vec![];
+    /// qt.query_touching(&region, 0.001, &mut touching);
+    /// assert_eq!(1, touching.len());
+    /// assert!(Rc::ptr_eq(&flush, &touching[0]));
+    /// ```
+    pub fn query_touching(
+        &self,
+        rect: &dyn Bounded<T>,
+        epsilon: T,
+        out: &mut Vec<Rc<dyn Bounded<T>>>,
+    ) {
+        let grown = Edges {
+            north: rect.north_edge() + epsilon,
+            east: rect.east_edge() + epsilon,
+            south: rect.south_edge() - epsilon,
+            west: rect.west_edge() - epsilon,
+        };
+        self.for_each_in_rect(&grown, |rc| {
+            // negative if the object and `rect` are apart along the axis
+            let overlap_x =
+                rc.east_edge().min(rect.east_edge()) - rc.west_edge().max(rect.west_edge());
+            let overlap_y =
+                rc.north_edge().min(rect.north_edge()) - rc.south_edge().max(rect.south_edge());
+            if overlap_x.abs() <= epsilon && overlap_y > epsilon
+                || overlap_y.abs() <= epsilon && overlap_x > epsilon
+            {
+                out.push(Rc::clone(rc));
+            }
+        });
+    }
+
+    /// Pushes objects overlapping `rect` into `out` like `get_rect`, but stops as soon as `out`
+    /// holds `limit` objects. Returns `true` if more overlapping objects exist beyond the limit.
+    /// The traversal ends at the first object past the limit, so the remaining quadrants are
+    /// never visited.
+    ///
+    /// # Examples
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use spatialize::quadtree::{Aabb, Bounded, Quadtree};
+    /// let mut qt = Quadtree::new(-10.0, 10.0, 20.0, 20.0);
+    /// for i in 0..5 {
+    ///     let x = -9.0 + 3.0 * i as f32;
+    ///     qt.insert(Rc::new(Aabb::new(x, 0.0, x + 1.0, 1.0))).unwrap();
+    /// }
+    ///
+    /// let rect_view = Aabb::new(-10.0, -10.0, 10.0, 10.0);
+    /// let mut result_vec: Vec<Rc<dyn Bounded>> = vec![];
+    /// assert!(qt.get_rect_limited(&rect_view, 3, &mut result_vec));
+    /// assert_eq!(3, result_vec.len());
+    ///
+    /// let mut result_vec: Vec<Rc<dyn Bounded>> = vec![];
+    /// assert!(!qt.get_rect_limited(&rect_view, 5, &mut result_vec));
+    /// assert_eq!(5, result_vec.len());
+    /// ```
+    pub fn get_rect_limited(
+        &self,
+        rect: &dyn Bounded<T>,
+        limit: usize,
+        out: &mut Vec<Rc<dyn Bounded<T>>>,
+    ) -> bool {
+        if self.intersects(rect) {
+            for rc_ref in self.quadrants() {
+                if rc_ref.borrow().get_rect_limited(rect, limit, out) {
+                    return true;
+                }
+            }
+            for rc in self.node_contents() {
+                if rc.intersects(rect) {
+                    if out.len() >= limit {
+                        return true;
+                    }
+                    out.push(rc);
+                }
+            }
+        }
+        false
+    }
+
+    /// Returns up to `k` objects ordered by the distance from the point `(x, y)` to their edges,
+    /// nearest first. Ties are broken arbitrarily.
+    ///
+    /// Quadrants are visited closest first, and the search stops once no remaining quadrant
+    /// can be closer than the `k`-th best object found so far.
+    ///
+    /// # Examples
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use spatialize::quadtree::{Aabb, Bounded, Quadtree};
+    /// let mut qt = Quadtree::new(-10.0, 10.0, 20.0, 20.0);
+    /// let near: Rc<dyn Bounded> = Rc::new(Aabb::new(1.0, 0.0, 2.0, 1.0));
+    /// let middle: Rc<dyn Bounded> = Rc::new(Aabb::new(-5.0, 4.0, -4.0, 5.0));
+    /// let far: Rc<dyn Bounded> = Rc::new(Aabb::new(8.0, -9.0, 9.0, -8.0));
+    /// qt.insert(Rc::clone(&near)).unwrap();
+    /// qt.insert(Rc::clone(&middle)).unwrap();
+    /// qt.insert(Rc::clone(&far)).unwrap();
+    ///
+    /// let nearest = qt.k_nearest(0.0, 0.0, 2);
+    /// assert_eq!(2, nearest.len());
+    /// assert!(Rc::ptr_eq(&near, &nearest[0]));
+    /// assert!(Rc::ptr_eq(&middle, &nearest[1]));
+    /// ```
+    pub fn k_nearest(&self, x: T, y: T, k: usize) -> Vec<Rc<dyn Bounded<T>>> {
+        self.k_nearest_excluding(x, y, k, &[])
+    }
+
+    /// Returns up to `k` objects closest to the point `(x, y)` like `k_nearest`, skipping every
+    /// object in `exclude`, compared by pointer like `Rc::ptr_eq`. Excluded objects don't count
+    /// towards `k`, so no results are lost to filtering afterwards. Each candidate is checked
+    /// against every excluded object, which is cheap for small `exclude` sets.
+    ///
+    /// # Examples
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use spatialize::quadtree::{Aabb, Bounded, Quadtree};
+    /// let mut qt = Quadtree::new(-10.0, 10.0, 20.0, 20.0);
+    /// let near: Rc<dyn Bounded> = Rc::new(Aabb::new(1.0, 0.0, 2.0, 1.0));
+    /// let middle: Rc<dyn Bounded> = Rc::new(Aabb::new(-5.0, 4.0, -4.0, 5.0));
+    /// let far: Rc<dyn Bounded> = Rc::new(Aabb::new(8.0, -9.0, 9.0, -8.0));
+    /// qt.insert(Rc::clone(&near)).unwrap();
+    /// qt.insert(Rc::clone(&middle)).unwrap();
+    /// qt.insert(Rc::clone(&far)).unwrap();
+    ///
+    /// let nearest = qt.k_nearest_excluding(0.0, 0.0, 2, &[Rc::clone(&near)]);
+    /// assert_eq!(2, nearest.len());
+    /// assert!(Rc::ptr_eq(&middle, &nearest[0]));
+    /// assert!(Rc::ptr_eq(&far, &nearest[1]));
+    /// ```
+    pub fn k_nearest_excluding(
+        &self,
+        x: T,
+        y: T,
+        k: usize,
+        exclude: &[Rc<dyn Bounded<T>>],
+    ) -> Vec<Rc<dyn Bounded<T>>> {
+        let mut nearest = BinaryHeap::new();
+        let mut pending = BinaryHeap::new();
+        if k > 0 {
+            self.collect_nearest(x, y, k, exclude, &mut nearest, &mut pending);
+        }
+        while let Some(Reverse(ByDistance(distance, rc_ref))) = pending.pop() {
+            if nearest.len() == k
+                && nearest
+                    .peek()
+                    .is_some_and(|farthest: &ByDistance<T, _>| distance > farthest.0)
+            {
+                break;
+            }
+            rc_ref
+                .borrow()
+                .collect_nearest(x, y, k, exclude, &mut nearest, &mut pending);
+        }
+        nearest
+            .into_sorted_vec()
+            .into_iter()
+            .map(|ByDistance(_, rc)| rc)
+            .collect()
+    }
+
+    /// Returns the object closest to the point `(x, y)`, or `None` if the `Quadtree` is empty.
+    /// Like `k_nearest`, quadrants are searched closest first and the search stops as soon as
+    /// no remaining quadrant can be closer than the best object found so far.
+    ///
+    /// # Examples
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use spatialize::quadtree::{Aabb, Bounded, Quadtree};
+    /// let mut qt = Quadtree::new(-10.0, 10.0, 20.0, 20.0);
+    /// assert!(qt.nearest(0.0, 0.0).is_none());
+    ///
+    /// let near: Rc<dyn Bounded> = Rc::new(Aabb::new(1.0, 0.0, 2.0, 1.0));
+    /// let far: Rc<dyn Bounded> = Rc::new(Aabb::new(8.0, -9.0, 9.0, -8.0));
+    /// qt.insert(Rc::clone(&near)).unwrap();
+    /// qt.insert(Rc::clone(&far)).unwrap();
+    ///
+    /// assert!(Rc::ptr_eq(&near, &qt.nearest(0.0, 0.0).unwrap()));
+    /// assert!(Rc::ptr_eq(&far, &qt.nearest(9.0, -9.0).unwrap()));
+    /// ```
+    pub fn nearest(&self, x: T, y: T) -> Option<Rc<dyn Bounded<T>>> {
+        self.k_nearest(x, y, 1).pop()
+    }
+
+    /// Returns the object closest to the point `(x, y)` along with its distance, if it lies
+    /// within `radius`, or `None` if no object is that close. Searches like `nearest`, but also
+    /// skips every quadrant farther than `radius` from the point.
+    ///
+    /// # Examples
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use spatialize::quadtree::{Aabb, Bounded, Quadtree};
+    /// let mut qt = Quadtree::new(-10.0, 10.0, 20.0, 20.0);
+    /// let pickup: Rc<dyn Bounded> = Rc::new(Aabb::new(3.0, 0.0, 4.0, 1.0));
+    /// qt.insert(Rc::clone(&pickup)).unwrap();
+    /// qt.insert(Rc::new(Aabb::new(8.0, -9.0, 9.0, -8.0))).unwrap();
+    ///
+    /// let (nearest, distance) = qt.nearest_within(0.0, 0.0, 5.0).unwrap();
+    /// assert!(Rc::ptr_eq(&pickup, &nearest));
+    /// assert_eq!(3.0, distance);
+    /// assert!(qt.nearest_within(0.0, 0.0, 2.0).is_none());
+    /// ```
+    pub fn nearest_within(&self, x: T, y: T, radius: T) -> Option<(Rc<dyn Bounded<T>>, T)> {
+        if radius < T::zero() {
+            return None;
+        }
+        let limit = radius * radius;
+        let mut nearest = BinaryHeap::new();
+        let mut pending = BinaryHeap::new();
+        self.collect_nearest(x, y, 1, &[], &mut nearest, &mut pending);
+        while let Some(Reverse(ByDistance(distance, rc_ref))) = pending.pop() {
+            if distance > limit
+                || nearest
+                    .peek()
+                    .is_some_and(|closest: &ByDistance<T, _>| distance > closest.0)
+            {
+                break;
+            }
+            rc_ref
+                .borrow()
+                .collect_nearest(x, y, 1, &[], &mut nearest, &mut pending);
+        }
+        nearest
+            .pop()
+            .filter(|ByDistance(distance, _)| *distance <= limit)
+            .map(|ByDistance(distance, rc)| (rc, distance.sqrt()))
+    }
+
+    /// Returns the distance from the point `(x, y)` to the closest object, or `None` if the
+    /// `Quadtree` is empty. Searches like `nearest`, but only keeps track of the best distance
+    /// instead of the closest object. Points inside an object have a distance of `0`.
+    ///
+    /// # Examples
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use spatialize::quadtree::{Aabb, Bounded, Quadtree};
+    /// let mut qt = Quadtree::new(-10.0, 10.0, 20.0, 20.0);
+    /// assert!(qt.nearest_distance(0.0, 0.0).is_none());
+    ///
+    /// qt.insert(Rc::new(Aabb::new(3.0, 3.0, 4.0, 4.0))).unwrap();
+    /// qt.insert(Rc::new(Aabb::new(-9.0, -6.0, -8.0, -5.0))).unwrap();
+    /// assert_eq!(Some(3.0), qt.nearest_distance(0.0, 3.5));
+    /// assert_eq!(Some(0.0), qt.nearest_distance(3.5, 3.5));
+    /// ```
+    pub fn nearest_distance(&self, x: T, y: T) -> Option<T> {
+        let mut best = None;
+        let mut pending = BinaryHeap::new();
+        self.collect_nearest_distance(x, y, &mut best, &mut pending);
+        while let Some(Reverse(ByDistance(distance, rc_ref))) = pending.pop() {
+            if best.is_some_and(|best| distance > best) {
+                break;
+            }
+            rc_ref
+                .borrow()
+                .collect_nearest_distance(x, y, &mut best, &mut pending);
+        }
+        best.map(T::sqrt)
+    }
+
+    /// A private function lowering `best` to the squared distance from `(x, y)` to the closest
+    /// object of this node and queueing its quadrants in `pending` like `collect_nearest`.
+    fn collect_nearest_distance(
+        &self,
+        x: T,
+        y: T,
+        best: &mut Option<T>,
+        pending: &mut BinaryHeap<Reverse<ByDistance<T, QuadrantRef<T>>>>,
+    ) {
+        for entry in self.contents.iter() {
+            entry.with(|rc| {
+                let distance = object_distance_squared(&**rc, x, y);
+                if best.is_none_or(|best| distance < best) {
+                    *best = Some(distance);
+                }
+            });
+        }
+        for rc_ref in self.quadrants() {
+            let distance = rc_ref.borrow().distance_squared(x, y);
+            pending.push(Reverse(ByDistance(distance, Rc::clone(rc_ref))));
+        }
+    }
+
+    /// A private function offering this node's objects not in `exclude` to the bounded
+    /// max-heap `nearest` and queueing its quadrants in `pending` by their distance to `(x, y)`.
+    fn collect_nearest(
+        &self,
+        x: T,
+        y: T,
+        k: usize,
+        exclude: &[Rc<dyn Bounded<T>>],
+        nearest: &mut BinaryHeap<ByDistance<T, Rc<dyn Bounded<T>>>>,
+        pending: &mut BinaryHeap<Reverse<ByDistance<T, QuadrantRef<T>>>>,
+    ) {
+        for rc in self.node_contents() {
+            if exclude.iter().any(|excluded| Rc::ptr_eq(excluded, &rc)) {
+                continue;
+            }
+            let distance = object_distance_squared(&*rc, x, y);
+            if nearest.len() < k {
+                nearest.push(ByDistance(distance, rc));
+            } else if nearest.peek().is_some_and(|farthest| distance < farthest.0) {
+                nearest.pop();
+                nearest.push(ByDistance(distance, rc));
+            }
+        }
+        for rc_ref in self.quadrants() {
+            let distance = rc_ref.borrow().distance_squared(x, y);
+            pending.push(Reverse(ByDistance(distance, Rc::clone(rc_ref))));
+        }
+    }
+
+    /// Pushes every object whose edges overlap the convex polygon through `points` into `out`.
+    /// Only quadrants whose bounds overlap the polygon are searched. The points may be given in
+    /// either winding order; polygons with fewer than three points return
+    /// `QuadtreeError::DegeneratePolygon`.
+    ///
+    /// # Examples
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use spatialize::quadtree::{Aabb, Bounded, Quadtree};
+    /// use spatialize::quadtree::QuadtreeError;
+    ///
+    /// let mut qt = Quadtree::new(-10.0, 10.0, 20.0, 20.0);
+    /// for col in 0..4 {
+    ///     for row in 0..4 {
+    ///         let (x, y) = (-8.0 + 4.0 * col as f32, 8.0 - 4.0 * row as f32);
+    ///         qt.insert(Rc::new(Aabb::new(x, y - 1.0, x + 1.0, y))).unwrap();
+    ///     }
+    /// }
+    ///
+    /// // the triangle above the diagonal y = x + 1 selects the rectangles with col + row <= 3
+    /// let triangle = [(-10.0, 10.0), (9.0, 10.0), (-10.0, -9.0)];
+    /// let mut selected: Vec<Rc<dyn Bounded>> = vec![];
+    /// qt.query_convex_polygon(&triangle, &mut selected).unwrap();
+    /// assert_eq!(10, selected.len());
+    /// assert!(selected.iter().all(|rc| rc.north_edge() >= rc.west_edge() + 1.0));
+    ///
+    /// assert_eq!(
+    ///     Err(QuadtreeError::DegeneratePolygon),
+    ///     qt.query_convex_polygon(&triangle[..2], &mut selected)
+    /// );
+    /// ```
+    pub fn query_convex_polygon(
+        &self,
+        points: &[(T, T)],
+        out: &mut Vec<Rc<dyn Bounded<T>>>,
+    ) -> Result<(), QuadtreeError> {
+        if points.len() < 3 {
+            return Err(QuadtreeError::DegeneratePolygon);
+        }
+        self.collect_in_polygon(points, out);
+        Ok(())
+    }
+
+    /// A private function doing the traversal of `query_convex_polygon`.
+    fn collect_in_polygon(&self, points: &[(T, T)], out: &mut Vec<Rc<dyn Bounded<T>>>) {
+        let edges = self.loose_edges();
+        if overlaps_polygon(points, edges.north, edges.east, edges.south, edges.west) {
+            for rc_ref in self.quadrants() {
+                rc_ref.borrow().collect_in_polygon(points, out);
+            }
+            for rc in self.node_contents() {
+                if overlaps_polygon(
+                    points,
+                    rc.north_edge(),
+                    rc.east_edge(),
+                    rc.south_edge(),
+                    rc.west_edge(),
+                ) {
+                    out.push(rc);
+                }
+            }
+        }
+    }
+
+    /// Pushes every object whose edges lie within `radius` of the point `(x, y)` into `out`.
+    /// Only quadrants whose bounds intersect the circle are searched.
+    ///
+    /// # Examples
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use spatialize::quadtree::{Aabb, Bounded, Quadtree};
+    /// let mut qt = Quadtree::new(-10.0, 10.0, 20.0, 20.0);
+    /// qt.insert(Rc::new(Aabb::new(1.0, 0.0, 2.0, 1.0))).unwrap();
+    /// qt.insert(Rc::new(Aabb::new(4.0, -5.0, 5.0, -4.0))).unwrap();
+    ///
+    /// let mut in_circle: Vec<Rc<dyn Bounded>> = vec![];
+    /// qt.query_circle(0.0, 0.0, 5.0, &mut in_circle);
+    /// assert_eq!(1, in_circle.len());
+    ///
+    /// let square = Aabb::new(-5.0, -5.0, 5.0, 5.0);
+    /// let mut in_square: Vec<Rc<dyn Bounded>> = vec![];
+    /// qt.get_rect(&square, &mut in_square).unwrap();
+    /// assert_eq!(2, in_square.len());
+    /// ```
+    pub fn query_circle(&self, x: T, y: T, radius: T, out: &mut Vec<Rc<dyn Bounded<T>>>) {
+        let radius_squared = radius * radius;
+        if self.distance_squared(x, y) <= radius_squared {
+            for rc_ref in self.quadrants() {
+                rc_ref.borrow().query_circle(x, y, radius, out);
+            }
+            for rc in self.node_contents() {
+                if object_distance_squared(&*rc, x, y) <= radius_squared {
+                    out.push(rc);
+                }
+            }
+        }
+    }
+
+    /// Pushes every object whose edges lie within `radius` of the point `(x, y)` into `out`
+    /// like `query_circle`, together with the distance from `(x, y)` to the edges of the
+    /// object, which is zero if the point lies inside the object.
+    ///
+    /// # Examples
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use spatialize::quadtree::{Aabb, Bounded, Quadtree};
+    /// let mut qt = Quadtree::new(-10.0, 10.0, 20.0, 20.0);
+    /// qt.insert(Rc::new(Aabb::new(-1.0, -1.0, 1.0, 1.0))).unwrap();
+    /// qt.insert(Rc::new(Aabb::new(3.0, 0.0, 4.0, 1.0))).unwrap();
+    /// qt.insert(Rc::new(Aabb::new(8.0, -9.0, 9.0, -8.0))).unwrap();
+    ///
+    /// let mut in_circle: Vec<(Rc<dyn Bounded>, f32)> = vec![];
+    /// qt.query_circle_with_distance(0.0, 0.0, 5.0, &mut in_circle);
+    /// in_circle.sort_by(|a, b| a.1.total_cmp(&b.1));
+    /// assert_eq!(2, in_circle.len());
+    /// assert_eq!(0.0, in_circle[0].1);
+    /// assert_eq!(3.0, in_circle[1].1);
+    /// ```
+    pub fn query_circle_with_distance(
+        &self,
+        x: T,
+        y: T,
+        radius: T,
+        out: &mut Vec<(Rc<dyn Bounded<T>>, T)>,
+    ) {
+        let radius_squared = radius * radius;
+        if self.distance_squared(x, y) <= radius_squared {
+            for rc_ref in self.quadrants() {
+                rc_ref
+                    .borrow()
+                    .query_circle_with_distance(x, y, radius, out);
+            }
+            for rc in self.node_contents() {
+                let distance_squared = object_distance_squared(&*rc, x, y);
+                if distance_squared <= radius_squared {
+                    out.push((rc, distance_squared.sqrt()));
+                }
+            }
+        }
+    }
+
+    /// Pushes every object overlapping `rect` into `out` together with the distance from the
+    /// point `(from_x, from_y)` to its edges, closest first. The sort is stable, so objects at
+    /// the same distance keep the order `get_rect` finds them in.
+    ///
+    /// # Examples
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use spatialize::quadtree::{Aabb, Bounded, Quadtree};
+    /// let mut qt = Quadtree::new(-10.0, 10.0, 20.0, 20.0);
+    /// qt.insert(Rc::new(Aabb::new(6.0, 0.0, 7.0, 1.0))).unwrap();
+    /// qt.insert(Rc::new(Aabb::new(2.0, 0.0, 3.0, 1.0))).unwrap();
+    /// qt.insert(Rc::new(Aabb::new(-8.0, -9.0, -7.0, -8.0))).unwrap();
+    ///
+    /// let rect_view = Aabb::new(0.0, -10.0, 10.0, 10.0);
+    /// let mut by_distance: Vec<(Rc<dyn Bounded>, f32)> = vec![];
+    /// qt.query_rect_by_distance(&rect_view, 0.0, 0.0, &mut by_distance);
+    /// let distances: Vec<f32> = by_distance.iter().map(|(_, distance)| *distance).collect();
+    /// assert_eq!(vec![2.0, 6.0], distances);
+    /// ```
+    pub fn query_rect_by_distance(
+        &self,
+        rect: &dyn Bounded<T>,
+        from_x: T,
+        from_y: T,
+        out: &mut Vec<(Rc<dyn Bounded<T>>, T)>,
+    ) {
+        let start = out.len();
+        self.for_each_in_rect(rect, |rc| {
+            let distance = object_distance_squared(&**rc, from_x, from_y).sqrt();
+            out.push((Rc::clone(rc), distance));
+        });
+        out[start..].sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(Ordering::Equal));
+    }
+
+    /// Pushes every object stored in the nodes containing the point `(x, y)` into `out`,
+    /// together with the distance from `(x, y)` to the edges of the object, which is zero if
+    /// the point lies inside the object. These are the objects sharing a quadrant with the
+    /// point, so unlike `query_point` it also reports nearby objects not enclosing the point.
+    /// Nothing is pushed if the point lies outside the `Quadtree`.
+    ///
+    /// # Examples
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use spatialize::quadtree::{Aabb, Bounded, Quadtree};
+    /// let mut qt = Quadtree::new(-10.0, 10.0, 20.0, 20.0);
+    /// qt.insert(Rc::new(Aabb::new(1.0, 2.0, 3.0, 4.0))).unwrap();
+    /// qt.insert(Rc::new(Aabb::new(2.0, 4.0, 5.0, 5.0))).unwrap();
+    /// qt.insert(Rc::new(Aabb::new(-8.0, -8.0, -6.0, -6.0))).unwrap();
+    ///
+    /// let mut near: Vec<(Rc<dyn Bounded>, f32)> = vec![];
+    /// qt.query_point_with_distance(2.0, 3.0, &mut near);
+    /// near.sort_by(|a, b| a.1.total_cmp(&b.1));
+    /// assert_eq!(2, near.len());
+    /// assert_eq!(0.0, near[0].1);
+    /// assert_eq!(1.0, near[1].1);
+    /// ```
+    pub fn query_point_with_distance(&self, x: T, y: T, out: &mut Vec<(Rc<dyn Bounded<T>>, T)>) {
+        if self.loose_edges().contains_point(x, y) {
+            for rc_ref in self.quadrants() {
+                rc_ref.borrow().query_point_with_distance(x, y, out);
+            }
+            for rc in self.node_contents() {
+                let distance = object_distance_squared(&*rc, x, y).sqrt();
+                out.push((rc, distance));
+            }
+        }
+    }
+
+    /// Pushes every object whose edges enclose the point `(x, y)` into `out`, walking down the
+    /// quadrants containing the point and checking the objects stored at each node on the way.
+    /// A point exactly on the border between quadrants is looked up in all of them.
+    /// Nothing is pushed if the point lies outside the `Quadtree`.
+    ///
+    /// # Examples
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use spatialize::quadtree::{Aabb, Bounded, Quadtree};
+    /// let mut qt = Quadtree::new(-10.0, 10.0, 20.0, 20.0);
+    /// qt.insert(Rc::new(Aabb::new(-5.0, -5.0, 5.0, 5.0))).unwrap();
+    /// qt.insert(Rc::new(Aabb::new(1.0, 2.0, 3.0, 4.0))).unwrap();
+    /// qt.insert(Rc::new(Aabb::new(-8.0, -8.0, -6.0, -6.0))).unwrap();
+    ///
+    /// let mut hits: Vec<Rc<dyn Bounded>> = vec![];
+    /// qt.query_point(2.0, 3.0, &mut hits);
+    /// assert_eq!(2, hits.len());
+    /// ```
+    pub fn query_point(&self, x: T, y: T, out: &mut Vec<Rc<dyn Bounded<T>>>) {
+        if self.loose_edges().contains_point(x, y) {
+            for rc_ref in self.quadrants() {
+                rc_ref.borrow().query_point(x, y, out);
+            }
+            for rc in self.node_contents() {
+                if rc.contains_point(x, y) {
+                    out.push(rc);
+                }
+            }
+        }
+    }
+
+    /// Returns an SVG document drawing the bounds of every node as an outlined rectangle and
+    /// the edges of every stored object as a filled rectangle.
+    ///
+    /// The SVG y-axis points down, so y coordinates are negated to keep the orientation of the
+    /// `Quadtree`, where north is up.
+    ///
+    /// # Examples
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use spatialize::quadtree::{Aabb, Bounded, Quadtree};
+    /// let mut qt = Quadtree::with_limits(-10.0, 10.0, 20.0, 20.0, 0, 1);
+    /// qt.insert(Rc::new(Aabb::new(0.0, -5.0, 5.0, 0.0))).unwrap();
+    ///
+    /// // The root, its four quadrants and the object.
+    /// assert_eq!(6, qt.to_svg().matches("<rect").count());
+    /// ```
+    pub fn to_svg(&self) -> String {
+        let number = |value: T| value.to_f64().unwrap_or_default();
+        let mut svg = String::new();
+        let _ = writeln!(
+            svg,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="{} {} {} {}">"#,
+            number(self.position_x),
+            -number(self.position_y),
+            number(self.width),
+            number(self.height),
+        );
+        self.write_svg(&mut svg);
+        svg.push_str("</svg>\n");
+        svg
+    }
+
+    /// A private function appending the rectangles of this node and its quadrants to `svg`.
+    fn write_svg(&self, svg: &mut String) {
+        let number = |value: T| value.to_f64().unwrap_or_default();
+        let _ = writeln!(
+            svg,
+            r#"<rect x="{}" y="{}" width="{}" height="{}" fill="none" stroke="black" vector-effect="non-scaling-stroke"/>"#,
+            number(self.position_x),
+            -number(self.position_y),
+            number(self.width),
+            number(self.height),
+        );
+        for rc in self.node_contents() {
+            let _ = writeln!(
+                svg,
+                r#"<rect x="{}" y="{}" width="{}" height="{}" fill="red" fill-opacity="0.5"/>"#,
+                number(rc.west_edge()),
+                -number(rc.north_edge()),
+                number(rc.east_edge() - rc.west_edge()),
+                number(rc.north_edge() - rc.south_edge()),
+            );
+        }
+        for rc_ref in self.quadrants() {
+            rc_ref.borrow().write_svg(svg);
+        }
+    }
+
+    /// Checks the structure of this node and every node below it, returning a description of
+    /// the first violation found. Meant as a debugging aid, e.g. to catch `Bounded` impls whose
+    /// edges change without calling `update`.
+    ///
+    /// Every stored object has to lie within the bounds of its node, or overlap them in the
+    /// root, which also holds objects inserted with `insert_clamped`. A divided node has to
+    /// have all four quadrants, each one level deeper and covering its quarter of the node, and
+    /// an undivided node none.
+    ///
+    /// # Examples
+    /// ```
+    /// use std::cell::Cell;
+    /// use std::rc::Rc;
+    /// use spatialize::quadtree::{Bounded, Quadtree};
+    ///
+    /// #[derive(Debug)]
+    /// struct Particle {
+    ///     x: Cell<f32>,
+    ///     y: Cell<f32>,
+    /// }
+    ///
+    /// impl Bounded for Particle {
+    ///     fn north_edge(&self) -> f32 {
+    ///         self.y.get()
+    ///     }
+    ///     fn east_edge(&self) -> f32 {
+    ///         self.x.get()
+    ///     }
+    ///     fn south_edge(&self) -> f32 {
+    ///         self.y.get()
+    ///     }
+    ///     fn west_edge(&self) -> f32 {
+    ///         self.x.get()
+    ///     }
+    /// }
+    ///
+    /// let mut qt = Quadtree::with_capacity(-10.0, 10.0, 20.0, 20.0, 1);
+    /// let particle = Rc::new(Particle { x: Cell::new(-5.0), y: Cell::new(5.0) });
+    /// qt.insert(particle.clone()).unwrap();
+    /// qt.insert(Rc::new(Particle { x: Cell::new(5.0), y: Cell::new(-5.0) })).unwrap();
+    /// assert_eq!(Ok(()), qt.check_invariants());
+    ///
+    /// // moved without calling `update`
+    /// particle.x.set(5.0);
+    /// assert!(qt.check_invariants().is_err());
+    /// ```
+    pub fn check_invariants(&self) -> Result<(), String> {
+        let number = |value: T| value.to_f64().unwrap_or(f64::NAN);
+        let bounds = (
+            number(self.position_x),
+            number(self.position_y),
+            number(self.width),
+            number(self.height),
+        );
+        for rc in self.node_contents() {
+            let holds = if self.depth == 0 {
+                self.intersects(&*rc)
+            } else {
+                self.fits(&*rc)
+            };
+            if !holds {
+                let mut message = String::new();
+                let _ = write!(
+                    message,
+                    "Object with edges {:?} lies outside the node with bounds {:?}.",
+                    (
+                        number(rc.north_edge()),
+                        number(rc.east_edge()),
+                        number(rc.south_edge()),
+                        number(rc.west_edge()),
+                    ),
+                    bounds,
+                );
+                return Err(message);
+            }
+        }
+
+        let half_width = self.width / two();
+        let half_height = self.height / two();
+        let quadrants = [
+            (
+                "northeast",
+                &self.northeast_quad,
+                self.position_x + half_width,
+                self.position_y,
+            ),
+            (
+                "northwest",
+                &self.northwest_quad,
+                self.position_x,
+                self.position_y,
+            ),
+            (
+                "southeast",
+                &self.southeast_quad,
+                self.position_x + half_width,
+                self.position_y - half_height,
+            ),
+            (
+                "southwest",
+                &self.southwest_quad,
+                self.position_x,
+                self.position_y - half_height,
+            ),
+        ];
+        // quadrants added by `insert_growing` are computed differently and may be off by rounding
+        let tolerance = (self.position_x.abs() + self.position_y.abs() + self.width + self.height)
+            * T::epsilon()
+            * two();
+        let close = |a: T, b: T| (a - b).abs() <= tolerance;
+        let mut message = String::new();
+        for (name, quadrant, position_x, position_y) in quadrants {
+            match (self.divided, quadrant) {
+                (true, None) => {
+                    let _ = write!(
+                        message,
+                        "Divided node with bounds {bounds:?} has no {name} quadrant."
+                    );
+                }
+                (false, Some(_)) => {
+                    let _ = write!(
+                        message,
+                        "Undivided node with bounds {bounds:?} has a {name} quadrant."
+                    );
+                }
+                (_, Some(rc_ref)) => {
+                    let quadrant = rc_ref.borrow();
+                    if quadrant.depth != self.depth + 1
+                        || !close(quadrant.position_x, position_x)
+                        || !close(quadrant.position_y, position_y)
+                        || !close(quadrant.width, half_width)
+                        || !close(quadrant.height, half_height)
+                    {
+                        let _ = write!(
+                            message,
+                            "The {name} quadrant of the node with bounds {bounds:?} has bounds \
+                             {:?} at depth {} instead of {:?} at depth {}.",
+                            (
+                                number(quadrant.position_x),
+                                number(quadrant.position_y),
+                                number(quadrant.width),
+                                number(quadrant.height),
+                            ),
+                            quadrant.depth,
+                            (
+                                number(position_x),
+                                number(position_y),
+                                number(half_width),
+                                number(half_height),
+                            ),
+                            self.depth + 1,
+                        );
+                    } else {
+                        quadrant.check_invariants()?;
+                    }
+                }
+                (false, None) => {}
+            }
+            if !message.is_empty() {
+                return Err(message);
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns every pair of stored objects whose edges overlap, without duplicates or pairs of
+    /// an object with itself. Each object is tested against the objects stored in the same
+    /// node and in its ancestors, and objects reaching to the border of a sibling quadrant
+    /// against the objects of that quadrant. Like `Bounded::intersects`, objects touching
+    /// along an edge count as overlapping, wherever they are stored.
+    ///
+    /// # Examples
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use spatialize::quadtree::{Aabb, Bounded, Quadtree};
+    /// let mut qt = Quadtree::new(-10.0, 10.0, 20.0, 20.0);
+    /// qt.insert(Rc::new(Aabb::new(-2.0, -1.0, 1.0, 2.0))).unwrap();
+    /// qt.insert(Rc::new(Aabb::new(-1.0, -2.0, 2.0, 1.0))).unwrap();
+    /// qt.insert(Rc::new(Aabb::new(0.0, -3.0, 3.0, 0.0))).unwrap();
+    /// qt.insert(Rc::new(Aabb::new(6.0, -7.0, 7.0, -6.0))).unwrap();
+    ///
+    /// assert_eq!(3, qt.overlapping_pairs().len());
+    /// ```
+    ///
+    /// The pairs are the same as when testing every two objects, regardless of how the
+    /// objects are spread over the quadrants.
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use spatialize::quadtree::{Aabb, Bounded, Quadtree};
+    /// let mut qt = Quadtree::with_capacity(-10.0, 10.0, 20.0, 20.0, 1);
+    /// qt.insert(Rc::new(Aabb::new(-3.0, 0.0, 0.0, 2.0))).unwrap();
+    /// qt.insert(Rc::new(Aabb::new(0.0, 0.0, 3.0, 2.0))).unwrap();
+    /// qt.insert(Rc::new(Aabb::new(-3.0, -2.0, -1.0, 0.0))).unwrap();
+    /// qt.insert(Rc::new(Aabb::new(5.0, -5.0, 6.0, -4.0))).unwrap();
+    ///
+    /// let objects: Vec<_> = qt.iter().collect();
+    /// let mut brute_force = 0;
+    /// for (index, sized_object) in objects.iter().enumerate() {
+    ///     for other in &objects[index + 1..] {
+    ///         if sized_object.intersects(&**other) {
+    ///             brute_force += 1;
+    ///         }
+    ///     }
+    /// }
+    /// assert_eq!(2, brute_force);
+    /// assert_eq!(brute_force, qt.overlapping_pairs().len());
+    /// ```
+    pub fn overlapping_pairs(&self) -> Vec<BoundedPair<T>> {
+        let mut pairs = vec![];
+        self.collect_pairs(None, &mut vec![], &mut pairs);
+        pairs
+    }
+
+    /// Returns every pair of stored objects whose edges overlap each other, where both objects
+    /// also overlap `rect`. The pairs are the ones of `overlapping_pairs` with both objects
+    /// overlapping `rect`, in the same order, but only quadrants overlapping `rect` are
+    /// searched.
+    ///
+    /// # Examples
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use spatialize::quadtree::{Aabb, Bounded, Quadtree};
+    /// let mut qt = Quadtree::with_capacity(-10.0, 10.0, 20.0, 20.0, 1);
+    /// qt.insert(Rc::new(Aabb::new(-8.0, 5.0, -5.0, 8.0))).unwrap();
+    /// qt.insert(Rc::new(Aabb::new(-6.0, 3.0, -3.0, 6.0))).unwrap();
+    /// qt.insert(Rc::new(Aabb::new(5.0, -8.0, 8.0, -5.0))).unwrap();
+    /// qt.insert(Rc::new(Aabb::new(6.0, -9.0, 9.0, -6.0))).unwrap();
+    ///
+    /// let region = Aabb::new(-10.0, 0.0, 0.0, 10.0);
+    /// let pairs = qt.overlapping_pairs_in_rect(&region);
+    /// assert_eq!(1, pairs.len());
+    ///
+    /// let filtered: Vec<_> = qt
+    ///     .overlapping_pairs()
+    ///     .into_iter()
+    ///     .filter(|(a, b)| a.intersects(&region) && b.intersects(&region))
+    ///     .collect();
+    /// assert_eq!(filtered.len(), pairs.len());
+    /// assert!(Rc::ptr_eq(&filtered[0].0, &pairs[0].0) && Rc::ptr_eq(&filtered[0].1, &pairs[0].1));
+    /// ```
+    pub fn overlapping_pairs_in_rect(&self, rect: &dyn Bounded<T>) -> Vec<BoundedPair<T>> {
+        let mut pairs = vec![];
+        self.collect_pairs(Some(rect), &mut vec![], &mut pairs);
+        pairs
+    }
+
+    /// A private function pushing the overlapping pairs among the objects of this node and
+    /// `ancestors`, and within its quadrants, into `pairs`. With a `rect`, only objects and
+    /// quadrants overlapping it are considered.
+    fn collect_pairs(
+        &self,
+        rect: Option<&dyn Bounded<T>>,
+        ancestors: &mut Vec<Rc<dyn Bounded<T>>>,
+        pairs: &mut Vec<BoundedPair<T>>,
+    ) {
+        if rect.is_some_and(|rect| !self.intersects(rect)) {
+            return;
+        }
+        let objects: Vec<_> = self
+            .node_contents()
+            .filter(|rc| rect.is_none_or(|rect| rc.intersects(rect)))
+            .collect();
+        for (index, rc) in objects.iter().enumerate() {
+            for other in ancestors.iter().chain(&objects[index + 1..]) {
+                if rc.intersects(&**other) {
+                    pairs.push((Rc::clone(other), Rc::clone(rc)));
+                }
+            }
+        }
+        let ancestor_count = ancestors.len();
+        ancestors.extend(objects);
+        for rc_ref in self.quadrants() {
+            rc_ref.borrow().collect_pairs(rect, ancestors, pairs);
+        }
+        ancestors.truncate(ancestor_count);
+        // sibling quadrants share their borders, and loose ones overlap each other
+        let quadrants: Vec<_> = self.quadrants().collect();
+        for (index, rc_ref) in quadrants.iter().enumerate() {
+            let quadrant = rc_ref.borrow();
+            for other_ref in &quadrants[index + 1..] {
+                let other = other_ref.borrow();
+                quadrant.for_each_in_rect(&other.loose_edges(), |rc| {
+                    if rect.is_some_and(|rect| !rc.intersects(rect)) {
+                        return;
+                    }
+                    other.for_each_in_rect(&**rc, |other_rc| {
+                        if rect.is_none_or(|rect| other_rc.intersects(rect)) {
+                            pairs.push((Rc::clone(rc), Rc::clone(other_rc)));
+                        }
+                    });
+                });
+            }
+        }
+    }
+
+    /// Pushes every pair of overlapping objects with one object from `a` and the other from
+    /// `b` into `out`, in that order. Both trees are descended together, so only quadrants of
+    /// `a` and `b` overlapping each other are compared. The trees may have different bounds.
+    ///
+    /// # Examples
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use spatialize::quadtree::{Aabb, Bounded, Quadtree};
+    /// let mut projectiles = Quadtree::new(-10.0, 10.0, 20.0, 20.0);
+    /// let mut enemies = Quadtree::new(-20.0, 20.0, 40.0, 40.0);
+    /// let bullet: Rc<dyn Bounded> = Rc::new(Aabb::new(1.0, 0.5, 1.5, 1.0));
+    /// projectiles.insert(Rc::clone(&bullet)).unwrap();
+    /// projectiles.insert(Rc::new(Aabb::new(-8.0, 7.5, -7.5, 8.0))).unwrap();
+    /// let grunt: Rc<dyn Bounded> = Rc::new(Aabb::new(0.0, 0.0, 2.0, 2.0));
+    /// enemies.insert(Rc::clone(&grunt)).unwrap();
+    /// enemies.insert(Rc::new(Aabb::new(15.0, -17.0, 17.0, -15.0))).unwrap();
+    ///
+    /// let mut pairs = vec![];
+    /// Quadtree::join(&projectiles, &enemies, &mut pairs);
+    /// assert_eq!(1, pairs.len());
+    /// assert!(Rc::ptr_eq(&bullet, &pairs[0].0) && Rc::ptr_eq(&grunt, &pairs[0].1));
+    /// ```
+    pub fn join(a: &Self, b: &Self, out: &mut Vec<BoundedPair<T>>) {
+        if !a.intersects_node(b) {
+            return;
+        }
+        for rc in a.node_contents() {
+            b.for_each_in_rect(&*rc, |other| out.push((Rc::clone(&rc), Rc::clone(other))));
+        }
+        for other in b.node_contents() {
+            for rc_ref in a.quadrants() {
+                rc_ref
+                    .borrow()
+                    .for_each_in_rect(&*other, |rc| out.push((Rc::clone(rc), Rc::clone(&other))));
+            }
+        }
+        for rc_ref in a.quadrants() {
+            for other_ref in b.quadrants() {
+                Self::join(&rc_ref.borrow(), &other_ref.borrow(), out);
+            }
+        }
+    }
+}
+
+/// Cloning a `Quadtree` deep-copies its structure, so every node of the clone is a new node
+/// that can be subdivided or pruned independently of the original. The stored objects are
+/// shared between both trees, as only their `Rc`s are cloned, not the objects themselves.
+///
+/// # Examples
+/// ```
+/// # use std::rc::Rc;
+/// # use spatialize::quadtree::{Aabb, Bounded, Quadtree};
+/// let mut qt = Quadtree::new(-10.0, 10.0, 20.0, 20.0);
+/// let sized_object: Rc<dyn Bounded> = Rc::new(Aabb::new(0.0, -5.0, 5.0, 0.0));
+/// qt.insert(Rc::clone(&sized_object)).unwrap();
+///
+/// let mut snapshot = qt.clone();
+/// assert!(Rc::ptr_eq(&sized_object, &snapshot.iter().next().unwrap()));
+/// snapshot.insert(Rc::new(Aabb::new(-8.0, 6.0, -6.0, 8.0))).unwrap();
+/// snapshot.southeast().unwrap().borrow_mut().clear();
+/// assert_eq!(1, snapshot.len());
+/// assert!(!snapshot.contains(&sized_object));
+/// assert_eq!(1, qt.len());
+/// assert!(qt.contains(&sized_object));
+/// ```
+impl<T: Float> Clone for Quadtree<T> {
+    fn clone(&self) -> Self {
+        let quadrant = |quad: &Option<QuadrantRef<T>>| {
+            quad.as_ref()
+                .map(|rc_ref| Rc::new(RefCell::new(rc_ref.borrow().clone())))
+        };
+        Self {
+            position_x: self.position_x,
+            position_y: self.position_y,
+            width: self.width,
+            height: self.height,
+            capacity: self.capacity,
+            depth: self.depth,
+            max_depth: self.max_depth,
+            divided: self.divided,
+            dedup: self.dedup,
+            looseness: self.looseness,
+            northeast_quad: quadrant(&self.northeast_quad),
+            northwest_quad: quadrant(&self.northwest_quad),
+            southeast_quad: quadrant(&self.southeast_quad),
+            southwest_quad: quadrant(&self.southwest_quad),
+            contents: self.contents.clone(),
+            ids: self.ids.clone(),
+            next_id: self.next_id,
+            hooks: self.hooks.clone(),
+        }
+    }
+}
+
+/// Two `Quadtree`s are equal if they have the same bounds and store the same objects,
+/// identified by pointer equality, regardless of how the objects are partitioned into
+/// quadrants. Capacity, maximum depth and insertion order don't matter, but an object stored
+/// twice has to be stored twice in the other tree as well.
+///
+/// # Examples
+/// ```
+/// # use std::rc::Rc;
+/// # use spatialize::quadtree::{Aabb, Bounded, Quadtree};
+/// let first: Rc<dyn Bounded> = Rc::new(Aabb::new(0.0, -5.0, 5.0, 0.0));
+/// let second: Rc<dyn Bounded> = Rc::new(Aabb::new(-8.0, 6.0, -6.0, 8.0));
+///
+/// let mut qt = Quadtree::new(-10.0, 10.0, 20.0, 20.0);
+/// qt.insert(Rc::clone(&first)).unwrap();
+/// qt.insert(Rc::clone(&second)).unwrap();
+/// let mut other = Quadtree::with_capacity(-10.0, 10.0, 20.0, 20.0, 8);
+/// other.insert(Rc::clone(&second)).unwrap();
+/// other.insert(Rc::clone(&first)).unwrap();
+/// assert_eq!(qt, other);
+///
+/// other.insert(Rc::new(Aabb::new(0.0, -5.0, 5.0, 0.0))).unwrap();
+/// assert_ne!(qt, other);
+/// ```
+impl<T: Float> PartialEq for Quadtree<T> {
+    fn eq(&self, other: &Self) -> bool {
+        let addresses = |quadtree: &Self| {
+            let mut addresses: Vec<*const ()> = quadtree
+                .iter()
+                .map(|rc| Rc::as_ptr(&rc) as *const ())
+                .collect();
+            addresses.sort_unstable();
+            addresses
+        };
+        self.bounds() == other.bounds() && addresses(self) == addresses(other)
+    }
+}
+
+/// Inserts every object of an iterator. As `extend` can't return errors, objects `insert`
+/// would reject, i.e. objects outside the bounds or duplicates in a tree built with
+/// `QuadtreeBuilder::dedup`, are silently skipped. Use `insert` or `from_bounds_and_iter` to
+/// find out about rejected objects.
+///
+/// # Examples
+/// ```
+/// # use std::rc::Rc;
+/// # use spatialize::quadtree::{Aabb, Bounded, Quadtree};
+/// let mut qt = Quadtree::new(-10.0, 10.0, 20.0, 20.0);
+/// let objects: Vec<Rc<dyn Bounded>> = vec![
+///     Rc::new(Aabb::new(0.0, -5.0, 5.0, 0.0)),
+///     Rc::new(Aabb::new(-8.0, 6.0, -6.0, 8.0)),
+///     Rc::new(Aabb::new(50.0, 48.0, 52.0, 50.0)),
+/// ];
+/// qt.extend(objects);
+/// assert_eq!(2, qt.len());
+/// ```
+impl<T: Float> Extend<Rc<dyn Bounded<T>>> for Quadtree<T> {
+    fn extend<I: IntoIterator<Item = Rc<dyn Bounded<T>>>>(&mut self, iter: I) {
+        for sized_object in iter {
+            let _ = self.insert(sized_object);
+        }
+    }
+}
+
+/// An iterator over every object stored in a `Quadtree`, created by `Quadtree::iter`.
+pub struct Iter<'a, T> {
+    contents: core::slice::Iter<'a, Entry<T>>,
+    pending: Vec<QuadrantRef<T>>,
+    current: Option<(QuadrantRef<T>, usize)>,
+    rect: Option<&'a dyn Bounded<T>>,
+}
+
+impl<T: Float> Iter<'_, T> {
+    /// A private function returning the object of `entry` if it is alive and overlaps the
+    /// queried rectangle, if any.
+    fn matching(&self, entry: &Entry<T>) -> Option<Rc<dyn Bounded<T>>> {
+        entry
+            .with(|rc| {
+                self.rect
+                    .is_none_or(|rect| rc.intersects(rect))
+                    .then(|| Rc::clone(rc))
+            })
+            .flatten()
+    }
+}
+
+impl<T: Float> Iterator for Iter<'_, T> {
+    type Item = Rc<dyn Bounded<T>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(entry) = self.contents.next() {
+            if let Some(rc) = self.matching(entry) {
+                return Some(rc);
+            }
+        }
+        loop {
+            if let Some((rc_ref, index)) = &self.current {
+                let node = rc_ref.borrow();
+                let mut index = *index;
+                while let Some(entry) = node.contents.get(index) {
+                    index += 1;
+                    if let Some(rc) = self.matching(entry) {
+                        drop(node);
+                        if let Some(current) = &mut self.current {
+                            current.1 = index;
+                        }
+                        return Some(rc);
+                    }
+                }
+            }
+            let rc_ref = self.pending.pop()?;
+            if self
+                .rect
+                .is_none_or(|rect| rc_ref.borrow().intersects(rect))
+            {
+                self.pending
+                    .extend(rc_ref.borrow().quadrants().rev().cloned());
+                self.current = Some((rc_ref, 0));
+            } else {
+                self.current = None;
+            }
         }
     }
 }
@@ -0,0 +1,132 @@
+use core::fmt::Debug;
+
+use num_traits::Float;
+
+use super::{two, Bounded};
+
+/// An axis-aligned bounding box implementing the `Bounded` trait, which can be inserted into
+/// a `Quadtree` or used as a query region without writing a custom type.
+///
+/// # Examples
+/// ```
+/// use std::rc::Rc;
+/// use spatialize::quadtree::{Aabb, Bounded, Quadtree};
+///
+/// let mut qt = Quadtree::new(-10.0, 10.0, 20.0, 20.0);
+/// qt.insert(Rc::new(Aabb::new(0.0, -5.0, 5.0, 0.0))).unwrap();
+///
+/// let mut result_vec: Vec<Rc<dyn Bounded>> = vec![];
+/// qt.get_rect_mode(
+///     &Aabb::from_center(0.0, 0.0, 2.0, 2.0),
+///     spatialize::quadtree::QueryMode::Overlap,
+///     &mut result_vec,
+/// );
+/// assert_eq!(1, result_vec.len());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Aabb<T = f32> {
+    pub min_x: T,
+    pub min_y: T,
+    pub max_x: T,
+    pub max_y: T,
+}
+
+impl<T: Float> Aabb<T> {
+    /// Returns an `Aabb` with the given minimum and maximum coordinates.
+    ///
+    /// # Examples
+    /// ```
+    /// # use spatialize::quadtree::Aabb;
+    /// let aabb = Aabb::new(-1.0, -2.0, 1.0, 2.0);
+    /// assert_eq!(2.0, aabb.max_y);
+    /// ```
+    pub fn new(min_x: T, min_y: T, max_x: T, max_y: T) -> Self {
+        Self {
+            min_x,
+            min_y,
+            max_x,
+            max_y,
+        }
+    }
+
+    /// Returns an `Aabb` centered on `(center_x, center_y)`, extending `half_width` to the west
+    /// and east and `half_height` to the south and north.
+    ///
+    /// # Examples
+    /// ```
+    /// # use spatialize::quadtree::Aabb;
+    /// assert_eq!(Aabb::new(-1.0, 1.0, 3.0, 5.0), Aabb::from_center(1.0, 3.0, 2.0, 2.0));
+    /// ```
+    pub fn from_center(center_x: T, center_y: T, half_width: T, half_height: T) -> Self {
+        Self::new(
+            center_x - half_width,
+            center_y - half_height,
+            center_x + half_width,
+            center_y + half_height,
+        )
+    }
+
+    /// Returns the smallest `Aabb` containing the two corners `(x0, y0)` and `(x1, y1)`, which
+    /// can be given in any order.
+    ///
+    /// # Examples
+    /// ```
+    /// # use spatialize::quadtree::Aabb;
+    /// assert_eq!(Aabb::new(-1.0, -2.0, 1.0, 2.0), Aabb::from_corners(1.0, -2.0, -1.0, 2.0));
+    /// ```
+    pub fn from_corners(x0: T, y0: T, x1: T, y1: T) -> Self {
+        Self::new(x0.min(x1), y0.min(y1), x0.max(x1), y0.max(y1))
+    }
+
+    /// Returns the width of the `Aabb`.
+    ///
+    /// # Examples
+    /// ```
+    /// # use spatialize::quadtree::Aabb;
+    /// assert_eq!(4.0, Aabb::from_center(0.0, 0.0, 2.0, 1.0).width());
+    /// ```
+    pub fn width(&self) -> T {
+        self.max_x - self.min_x
+    }
+
+    /// Returns the height of the `Aabb`.
+    ///
+    /// # Examples
+    /// ```
+    /// # use spatialize::quadtree::Aabb;
+    /// assert_eq!(2.0, Aabb::from_center(0.0, 0.0, 2.0, 1.0).height());
+    /// ```
+    pub fn height(&self) -> T {
+        self.max_y - self.min_y
+    }
+
+    /// Returns the center of the `Aabb` as `(x, y)`.
+    ///
+    /// # Examples
+    /// ```
+    /// # use spatialize::quadtree::Aabb;
+    /// assert_eq!((1.0, 3.0), Aabb::from_center(1.0, 3.0, 2.0, 2.0).center());
+    /// ```
+    pub fn center(&self) -> (T, T) {
+        (
+            (self.min_x + self.max_x) / two(),
+            (self.min_y + self.max_y) / two(),
+        )
+    }
+}
+
+impl<T: Float + Debug> Bounded<T> for Aabb<T> {
+    fn north_edge(&self) -> T {
+        self.max_y
+    }
+    fn east_edge(&self) -> T {
+        self.max_x
+    }
+    fn south_edge(&self) -> T {
+        self.min_y
+    }
+    fn west_edge(&self) -> T {
+        self.min_x
+    }
+}
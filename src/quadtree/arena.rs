@@ -0,0 +1,495 @@
+use alloc::rc::Rc;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use num_traits::Float;
+
+use super::{
+    encloses_point, object_distance_squared, out_of_bounds, two, Bounded, Edges, QuadtreeError,
+    DEFAULT_CAPACITY, DEFAULT_MAX_DEPTH,
+};
+
+/// A variant of the `Quadtree` storing all of its nodes in a single `Vec`, where a divided node
+/// refers to its quadrants by their index instead of through `Rc<RefCell<...>>`.
+///
+/// The four quadrants of a node are allocated next to each other, in the order northeast,
+/// northwest, southeast, southwest, so a node only stores the index of the first one. Nodes
+/// don't need a separate allocation or reference count each, and traversals walk a contiguous
+/// buffer. Objects are placed exactly as in the `Quadtree` with the same limits, but nodes
+/// aren't exposed individually.
+///
+/// # Examples
+/// ```
+/// # use std::rc::Rc;
+/// # use spatialize::quadtree::{Aabb, ArenaQuadtree, Bounded, Quadtree};
+/// let mut qt = ArenaQuadtree::new(-10.0, 10.0, 20.0, 20.0);
+/// let mut reference = Quadtree::new(-10.0, 10.0, 20.0, 20.0);
+/// let objects: [Rc<dyn Bounded>; 2] = [
+///     Rc::new(Aabb::new(0.0, -5.0, 5.0, 0.0)),
+///     Rc::new(Aabb::new(-8.0, 6.0, -6.0, 8.0)),
+/// ];
+/// for sized_object in objects {
+///     qt.insert(Rc::clone(&sized_object)).unwrap();
+///     reference.insert(sized_object).unwrap();
+/// }
+/// assert_eq!(reference.stats().node_count, qt.node_count());
+///
+/// let rect_view: Aabb = Aabb::new(-10.0, -10.0, 10.0, 10.0);
+/// let mut result_vec: Vec<Rc<dyn Bounded>> = vec![];
+/// qt.get_rect(&rect_view, &mut result_vec).unwrap();
+/// assert_eq!(2, result_vec.len());
+/// ```
+#[derive(Debug, Clone)]
+pub struct ArenaQuadtree<T = f32> {
+    capacity: usize,
+    max_depth: usize,
+    nodes: Vec<ArenaNode<T>>,
+}
+
+/// A private type holding a single node of an `ArenaQuadtree`.
+#[derive(Debug, Clone)]
+struct ArenaNode<T> {
+    position_x: T,
+    position_y: T,
+    width: T,
+    height: T,
+    depth: usize,
+    /// The index of the northeast quadrant, followed by the other three, if divided.
+    children: Option<usize>,
+    contents: Vec<Rc<dyn Bounded<T>>>,
+}
+
+impl<T: Float> ArenaNode<T> {
+    /// A private function returning an undivided, empty node with the given bounds.
+    fn new(position_x: T, position_y: T, width: T, height: T, depth: usize) -> Self {
+        Self {
+            position_x,
+            position_y,
+            width,
+            height,
+            depth,
+            children: None,
+            contents: vec![],
+        }
+    }
+
+    /// A private function checking whether `rect` overlaps the bounds of this node.
+    fn intersects(&self, rect: &dyn Bounded<T>) -> bool {
+        !(rect.north_edge() < self.position_y - self.height
+            || rect.east_edge() < self.position_x
+            || rect.south_edge() > self.position_y
+            || rect.west_edge() > self.position_x + self.width)
+    }
+
+    /// A private function checking whether `sized_object` lies within the bounds of this node.
+    fn fits(&self, sized_object: &dyn Bounded<T>) -> bool {
+        sized_object.north_edge() <= self.position_y
+            && sized_object.east_edge() <= self.position_x + self.width
+            && sized_object.south_edge() >= self.position_y - self.height
+            && sized_object.west_edge() >= self.position_x
+    }
+
+    /// A private function returning the squared distance from the point `(x, y)` to the
+    /// bounds of this node.
+    fn distance_squared(&self, x: T, y: T) -> T {
+        super::distance_squared(
+            self.position_y,
+            self.position_x + self.width,
+            self.position_y - self.height,
+            self.position_x,
+            x,
+            y,
+        )
+    }
+
+    /// A private function checking whether the point `(x, y)` lies within this node.
+    fn contains_point(&self, x: T, y: T) -> bool {
+        encloses_point(
+            self.position_y,
+            self.position_x + self.width,
+            self.position_y - self.height,
+            self.position_x,
+            x,
+            y,
+        )
+    }
+
+    /// A private function returning the indices of the quadrants of this node, which are empty
+    /// if it isn't divided.
+    fn quadrants(&self) -> core::ops::Range<usize> {
+        match self.children {
+            Some(first) => first..first + 4,
+            None => 0..0,
+        }
+    }
+}
+
+impl<T: Float> ArenaQuadtree<T> {
+    /// Returns an `ArenaQuadtree` with the specified boundaries and the `DEFAULT_CAPACITY`.
+    ///
+    /// # Examples
+    /// ```
+    /// # use spatialize::quadtree::ArenaQuadtree;
+    /// let qt: ArenaQuadtree = ArenaQuadtree::new(-100.0, 100.0, 200.0, 200.0);
+    /// ```
+    pub fn new(position_x: T, position_y: T, width: T, height: T) -> Self {
+        Self::with_capacity(position_x, position_y, width, height, DEFAULT_CAPACITY)
+    }
+
+    /// Returns an `ArenaQuadtree` with the specified boundaries, where each node holds up to
+    /// `capacity` objects before it subdivides.
+    ///
+    /// # Examples
+    /// ```
+    /// # use spatialize::quadtree::ArenaQuadtree;
+    /// let qt: ArenaQuadtree = ArenaQuadtree::with_capacity(-100.0, 100.0, 200.0, 200.0, 8);
+    /// ```
+    pub fn with_capacity(
+        position_x: T,
+        position_y: T,
+        width: T,
+        height: T,
+        capacity: usize,
+    ) -> Self {
+        Self::with_limits(
+            position_x,
+            position_y,
+            width,
+            height,
+            capacity,
+            DEFAULT_MAX_DEPTH,
+        )
+    }
+
+    /// Returns an `ArenaQuadtree` with the specified boundaries, node `capacity` and
+    /// `max_depth`.
+    ///
+    /// # Examples
+    /// ```
+    /// # use spatialize::quadtree::ArenaQuadtree;
+    /// let qt: ArenaQuadtree = ArenaQuadtree::with_limits(-100.0, 100.0, 200.0, 200.0, 8, 10);
+    /// ```
+    pub fn with_limits(
+        position_x: T,
+        position_y: T,
+        width: T,
+        height: T,
+        capacity: usize,
+        max_depth: usize,
+    ) -> Self {
+        Self {
+            capacity,
+            max_depth,
+            nodes: vec![ArenaNode::new(position_x, position_y, width, height, 0)],
+        }
+    }
+
+    /// Returns the bounds of the `ArenaQuadtree` as `(position_x, position_y, width, height)`.
+    ///
+    /// # Examples
+    /// ```
+    /// # use spatialize::quadtree::ArenaQuadtree;
+    /// let qt: ArenaQuadtree = ArenaQuadtree::new(-10.0, 10.0, 20.0, 20.0);
+    /// assert_eq!((-10.0, 10.0, 20.0, 20.0), qt.bounds());
+    /// ```
+    pub fn bounds(&self) -> (T, T, T, T) {
+        let root = &self.nodes[0];
+        (root.position_x, root.position_y, root.width, root.height)
+    }
+
+    /// Returns the number of nodes of the `ArenaQuadtree`, including the root.
+    ///
+    /// # Examples
+    /// ```
+    /// # use spatialize::quadtree::ArenaQuadtree;
+    /// let qt: ArenaQuadtree = ArenaQuadtree::new(-10.0, 10.0, 20.0, 20.0);
+    /// assert_eq!(1, qt.node_count());
+    /// ```
+    pub fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// A private function dividing the node at `index` into four quadrants appended to the
+    /// arena. Objects already stored in the node are left for the caller to redistribute.
+    fn subdivide(&mut self, index: usize) {
+        let node = &self.nodes[index];
+        if node.children.is_some() {
+            return;
+        }
+        let half_width = node.width / two();
+        let half_height = node.height / two();
+        let (west, north, depth) = (node.position_x, node.position_y, node.depth + 1);
+        let first = self.nodes.len();
+        self.nodes.extend([
+            ArenaNode::new(west + half_width, north, half_width, half_height, depth),
+            ArenaNode::new(west, north, half_width, half_height, depth),
+            ArenaNode::new(
+                west + half_width,
+                north - half_height,
+                half_width,
+                half_height,
+                depth,
+            ),
+            ArenaNode::new(west, north - half_height, half_width, half_height, depth),
+        ]);
+        self.nodes[index].children = Some(first);
+    }
+
+    /// A private function returning the quadrant of the node at `index` that `sized_object`
+    /// fits in, if any. Lower and then left quadrants win on boundaries, like in the
+    /// `Quadtree`.
+    fn fitting_quadrant(&self, index: usize, sized_object: &dyn Bounded<T>) -> Option<usize> {
+        self.nodes[index]
+            .quadrants()
+            .rev()
+            .find(|&quadrant| self.nodes[quadrant].fits(sized_object))
+    }
+
+    /// Inserts an object implementing the `Bounded` trait.
+    ///
+    /// Objects accumulate in a node until its capacity is exceeded, at which point the node
+    /// subdivides and moves its objects into the quadrants they fit in. Nodes at the maximum
+    /// depth never subdivide.
+    ///
+    /// # Examples
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use spatialize::quadtree::{Aabb, ArenaQuadtree, Bounded, QuadtreeError};
+    /// let mut qt = ArenaQuadtree::new(-10.0, 10.0, 20.0, 20.0);
+    /// assert!(qt.insert(Rc::new(Aabb::new(0.0, -5.0, 5.0, 0.0))).is_ok());
+    /// assert!(matches!(
+    ///     qt.insert(Rc::new(Aabb::new(20.0, 19.0, 21.0, 20.0))),
+    ///     Err(QuadtreeError::OutOfBounds { .. })
+    /// ));
+    /// ```
+    pub fn insert(&mut self, sized_object: Rc<dyn Bounded<T>>) -> Result<(), QuadtreeError> {
+        let root = &self.nodes[0];
+        if !root.fits(&*sized_object) {
+            let bounds = Edges {
+                north: root.position_y,
+                east: root.position_x + root.width,
+                south: root.position_y - root.height,
+                west: root.position_x,
+            };
+            return Err(out_of_bounds(&*sized_object, &bounds));
+        }
+        let mut pending = vec![(0, sized_object)];
+        while let Some((index, sized_object)) = pending.pop() {
+            if self.nodes[index].children.is_some() {
+                match self.fitting_quadrant(index, &*sized_object) {
+                    Some(quadrant) => pending.push((quadrant, sized_object)),
+                    None => self.nodes[index].contents.push(sized_object),
+                }
+                continue;
+            }
+            let node = &mut self.nodes[index];
+            node.contents.push(sized_object);
+            if node.contents.len() > self.capacity && node.depth < self.max_depth {
+                let contents = core::mem::take(&mut node.contents);
+                self.subdivide(index);
+                for sized_object in contents {
+                    match self.fitting_quadrant(index, &*sized_object) {
+                        Some(quadrant) => pending.push((quadrant, sized_object)),
+                        None => self.nodes[index].contents.push(sized_object),
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Removes an object previously inserted into the `ArenaQuadtree`, identified by pointer
+    /// equality. Only the quadrants the object currently fits in are searched. Nodes are kept
+    /// after they become empty.
+    ///
+    /// # Examples
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use spatialize::quadtree::{Aabb, ArenaQuadtree, Bounded, QuadtreeError};
+    /// let mut qt = ArenaQuadtree::new(-10.0, 10.0, 20.0, 20.0);
+    /// let sized_object: Rc<dyn Bounded> = Rc::new(Aabb::new(0.0, -5.0, 5.0, 0.0));
+    /// qt.insert(Rc::clone(&sized_object)).unwrap();
+    /// assert!(qt.remove(&sized_object).is_ok());
+    /// assert_eq!(Err(QuadtreeError::NotFound), qt.remove(&sized_object));
+    /// assert!(qt.is_empty());
+    /// ```
+    pub fn remove(&mut self, sized_object: &Rc<dyn Bounded<T>>) -> Result<(), QuadtreeError> {
+        let mut pending = vec![0];
+        while let Some(index) = pending.pop() {
+            let node = &mut self.nodes[index];
+            if !node.fits(&**sized_object) {
+                continue;
+            }
+            if let Some(position) = node
+                .contents
+                .iter()
+                .position(|rc| Rc::ptr_eq(rc, sized_object))
+            {
+                node.contents.remove(position);
+                return Ok(());
+            }
+            pending.extend(node.quadrants());
+        }
+        Err(QuadtreeError::NotFound)
+    }
+
+    /// Removes every object and node, keeping the bounds, capacity and maximum depth.
+    ///
+    /// # Examples
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use spatialize::quadtree::{Aabb, ArenaQuadtree, Bounded};
+    /// let mut qt = ArenaQuadtree::new(-10.0, 10.0, 20.0, 20.0);
+    /// qt.insert(Rc::new(Aabb::new(0.0, -5.0, 5.0, 0.0))).unwrap();
+    /// qt.clear();
+    /// assert!(qt.is_empty());
+    /// assert_eq!(1, qt.node_count());
+    /// ```
+    pub fn clear(&mut self) {
+        self.nodes.truncate(1);
+        let root = &mut self.nodes[0];
+        root.contents.clear();
+        root.children = None;
+    }
+
+    /// Returns the number of objects stored across all nodes of the `ArenaQuadtree`.
+    ///
+    /// # Examples
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use spatialize::quadtree::{Aabb, ArenaQuadtree, Bounded};
+    /// let mut qt = ArenaQuadtree::new(-10.0, 10.0, 20.0, 20.0);
+    /// qt.insert(Rc::new(Aabb::new(0.0, -5.0, 5.0, 0.0))).unwrap();
+    /// assert_eq!(1, qt.len());
+    /// ```
+    pub fn len(&self) -> usize {
+        self.nodes.iter().map(|node| node.contents.len()).sum()
+    }
+
+    /// Returns `true` if no objects are stored in the `ArenaQuadtree`.
+    ///
+    /// # Examples
+    /// ```
+    /// # use spatialize::quadtree::ArenaQuadtree;
+    /// let qt: ArenaQuadtree = ArenaQuadtree::new(-10.0, 10.0, 20.0, 20.0);
+    /// assert!(qt.is_empty());
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        self.nodes.iter().all(|node| node.contents.is_empty())
+    }
+
+    /// Calls `f` for every object overlapping `rect`. Only nodes whose bounds overlap `rect`
+    /// are visited.
+    ///
+    /// # Examples
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use spatialize::quadtree::{Aabb, ArenaQuadtree, Bounded};
+    /// let mut qt = ArenaQuadtree::new(-10.0, 10.0, 20.0, 20.0);
+    /// qt.insert(Rc::new(Aabb::new(0.0, -5.0, 5.0, 0.0))).unwrap();
+    /// let mut count = 0;
+    /// qt.for_each_in_rect(&Aabb::new(-2.0, -8.0, 8.0, 2.0), |_| count += 1);
+    /// assert_eq!(1, count);
+    /// ```
+    pub fn for_each_in_rect<F: FnMut(&Rc<dyn Bounded<T>>)>(&self, rect: &dyn Bounded<T>, mut f: F) {
+        self.visit(
+            |node| node.intersects(rect),
+            |rc| {
+                if rc.intersects(rect) {
+                    f(rc);
+                }
+            },
+        );
+    }
+
+    /// A private function calling `f` with the objects of every node for which `enter`
+    /// returns `true`, descending only into the quadrants of such nodes.
+    fn visit<E, F>(&self, mut enter: E, mut f: F)
+    where
+        E: FnMut(&ArenaNode<T>) -> bool,
+        F: FnMut(&Rc<dyn Bounded<T>>),
+    {
+        let mut pending = vec![0];
+        while let Some(index) = pending.pop() {
+            let node = &self.nodes[index];
+            if enter(node) {
+                node.contents.iter().for_each(&mut f);
+                pending.extend(node.quadrants());
+            }
+        }
+    }
+
+    /// Pushes every object overlapping `rect` into `vec`. A `rect` outside the bounds of the
+    /// `ArenaQuadtree` has no results and still returns `Ok`.
+    ///
+    /// # Examples
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use spatialize::quadtree::{Aabb, ArenaQuadtree, Bounded};
+    /// let mut qt = ArenaQuadtree::new(-10.0, 10.0, 20.0, 20.0);
+    /// qt.insert(Rc::new(Aabb::new(0.0, -5.0, 5.0, 0.0))).unwrap();
+    /// qt.insert(Rc::new(Aabb::new(-8.0, 6.0, -6.0, 8.0))).unwrap();
+    ///
+    /// let rect_view = Aabb::new(-2.0, -8.0, 8.0, 2.0);
+    /// let mut result_vec: Vec<Rc<dyn Bounded>> = vec![];
+    /// qt.get_rect(&rect_view, &mut result_vec).unwrap();
+    /// assert_eq!(1, result_vec.len());
+    /// ```
+    pub fn get_rect(
+        &self,
+        rect: &dyn Bounded<T>,
+        vec: &mut Vec<Rc<dyn Bounded<T>>>,
+    ) -> Result<(), QuadtreeError> {
+        self.for_each_in_rect(rect, |rc| vec.push(Rc::clone(rc)));
+        Ok(())
+    }
+
+    /// Pushes every object whose edges lie within `radius` of the point `(x, y)` into `out`.
+    ///
+    /// # Examples
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use spatialize::quadtree::{Aabb, ArenaQuadtree, Bounded};
+    /// let mut qt = ArenaQuadtree::new(-10.0, 10.0, 20.0, 20.0);
+    /// qt.insert(Rc::new(Aabb::new(1.0, 0.0, 2.0, 1.0))).unwrap();
+    /// qt.insert(Rc::new(Aabb::new(4.0, -5.0, 5.0, -4.0))).unwrap();
+    /// let mut in_circle: Vec<Rc<dyn Bounded>> = vec![];
+    /// qt.query_circle(0.0, 0.0, 5.0, &mut in_circle);
+    /// assert_eq!(1, in_circle.len());
+    /// ```
+    pub fn query_circle(&self, x: T, y: T, radius: T, out: &mut Vec<Rc<dyn Bounded<T>>>) {
+        let radius_squared = radius * radius;
+        self.visit(
+            |node| node.distance_squared(x, y) <= radius_squared,
+            |rc| {
+                if object_distance_squared(&**rc, x, y) <= radius_squared {
+                    out.push(Rc::clone(rc));
+                }
+            },
+        );
+    }
+
+    /// Pushes every object whose edges enclose the point `(x, y)` into `out`.
+    ///
+    /// # Examples
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use spatialize::quadtree::{Aabb, ArenaQuadtree, Bounded};
+    /// let mut qt = ArenaQuadtree::new(-10.0, 10.0, 20.0, 20.0);
+    /// qt.insert(Rc::new(Aabb::new(-5.0, -5.0, 5.0, 5.0))).unwrap();
+    /// qt.insert(Rc::new(Aabb::new(1.0, 2.0, 3.0, 4.0))).unwrap();
+    /// let mut hits: Vec<Rc<dyn Bounded>> = vec![];
+    /// qt.query_point(2.0, 3.0, &mut hits);
+    /// assert_eq!(2, hits.len());
+    /// ```
+    pub fn query_point(&self, x: T, y: T, out: &mut Vec<Rc<dyn Bounded<T>>>) {
+        self.visit(
+            |node| node.contains_point(x, y),
+            |rc| {
+                if rc.contains_point(x, y) {
+                    out.push(Rc::clone(rc));
+                }
+            },
+        );
+    }
+}
@@ -0,0 +1,85 @@
+//! Conversions to and from the `bevy_math` types and a broad phase built on the `Quadtree`,
+//! available with the `bevy` feature.
+
+use alloc::rc::Rc;
+use std::collections::HashMap;
+
+use bevy_math::{Rect, Vec2};
+
+use super::{Aabb, Bounded, Quadtree, QuadtreeError};
+
+impl From<Rect> for Aabb<f32> {
+    fn from(rect: Rect) -> Self {
+        Aabb::new(rect.min.x, rect.min.y, rect.max.x, rect.max.y)
+    }
+}
+
+impl From<Aabb<f32>> for Rect {
+    fn from(aabb: Aabb<f32>) -> Self {
+        Rect::new(aabb.min_x, aabb.min_y, aabb.max_x, aabb.max_y)
+    }
+}
+
+/// A broad phase for 2D games storing entity indices by their bounds, built on a `Quadtree`
+/// of `Aabb`s. Requires the `bevy` feature.
+///
+/// # Examples
+/// ```
+/// # use bevy_math::{Rect, Vec2};
+/// # use spatialize::quadtree::BroadPhase;
+/// let mut broad_phase = BroadPhase::new(Rect::new(-100.0, -100.0, 100.0, 100.0));
+/// broad_phase.insert(Vec2::new(0.0, 0.0), Vec2::new(2.0, 2.0), 7).unwrap();
+/// broad_phase.insert(Vec2::new(50.0, 50.0), Vec2::new(52.0, 52.0), 8).unwrap();
+///
+/// let mut indices = vec![];
+/// broad_phase.query(Rect::new(-1.0, -1.0, 1.0, 1.0), &mut indices);
+/// assert_eq!(vec![7], indices);
+/// ```
+#[derive(Debug)]
+pub struct BroadPhase {
+    quadtree: Quadtree<f32>,
+    indices: HashMap<*const (), usize>,
+}
+
+impl BroadPhase {
+    /// Returns an empty `BroadPhase` covering `bounds`.
+    pub fn new(bounds: Rect) -> Self {
+        Self {
+            quadtree: Quadtree::new(bounds.min.x, bounds.max.y, bounds.width(), bounds.height()),
+            indices: HashMap::new(),
+        }
+    }
+
+    /// Inserts the entity `index` with the bounds spanned by the corners `min` and `max`,
+    /// returning `QuadtreeError::OutOfBounds` if they don't fit within the `BroadPhase`.
+    pub fn insert(&mut self, min: Vec2, max: Vec2, index: usize) -> Result<(), QuadtreeError> {
+        let sized_object: Rc<dyn Bounded> = Rc::new(Aabb::new(min.x, min.y, max.x, max.y));
+        self.quadtree.insert(Rc::clone(&sized_object))?;
+        self.indices
+            .insert(Rc::as_ptr(&sized_object) as *const (), index);
+        Ok(())
+    }
+
+    /// Pushes the index of every entity whose bounds overlap `rect` into `out`.
+    pub fn query(&self, rect: Rect, out: &mut Vec<usize>) {
+        self.quadtree.for_each_in_rect(&Aabb::from(rect), |rc| {
+            out.extend(self.indices.get(&(Rc::as_ptr(rc) as *const ())))
+        });
+    }
+
+    /// Removes every entity, keeping the bounds.
+    pub fn clear(&mut self) {
+        self.quadtree.clear();
+        self.indices.clear();
+    }
+
+    /// Returns the number of entities in the `BroadPhase`.
+    pub fn len(&self) -> usize {
+        self.indices.len()
+    }
+
+    /// Returns `true` if the `BroadPhase` holds no entities.
+    pub fn is_empty(&self) -> bool {
+        self.indices.is_empty()
+    }
+}
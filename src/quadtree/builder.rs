@@ -0,0 +1,233 @@
+use alloc::rc::Rc;
+use core::cell::RefCell;
+
+use num_traits::Float;
+
+use super::{strict, Bounded, Hooks, Quadtree, DEFAULT_CAPACITY, DEFAULT_MAX_DEPTH};
+
+/// A builder configuring the bounds, node capacity, maximum depth, duplicate policy,
+/// looseness and instrumentation hooks of a `Quadtree` before creating it.
+///
+/// Every setting not given to the builder keeps the default of `Quadtree::new`, except for the
+/// bounds, which default to an empty box at the origin and should always be set.
+///
+/// # Examples
+/// ```
+/// # use std::rc::Rc;
+/// # use spatialize::quadtree::{Aabb, Bounded, QuadtreeBuilder, QuadtreeError};
+/// let mut qt = QuadtreeBuilder::new()
+///     .bounds(-10.0, 10.0, 20.0, 20.0)
+///     .capacity(4)
+///     .max_depth(8)
+///     .dedup(true)
+///     .build();
+///
+/// let sized_object: Rc<dyn Bounded> = Rc::new(Aabb::new(0.0, -5.0, 5.0, 0.0));
+/// qt.insert(Rc::clone(&sized_object)).unwrap();
+/// assert_eq!(Err(QuadtreeError::AlreadyPresent), qt.insert(sized_object));
+/// assert_eq!(1, qt.len());
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct QuadtreeBuilder<T = f32> {
+    bounds: (T, T, T, T),
+    capacity: usize,
+    max_depth: usize,
+    dedup: bool,
+    looseness: T,
+    contents_capacity: usize,
+    hooks: Hooks<T>,
+}
+
+impl<T: Float> Default for QuadtreeBuilder<T> {
+    fn default() -> Self {
+        Self {
+            bounds: (T::zero(), T::zero(), T::zero(), T::zero()),
+            capacity: DEFAULT_CAPACITY,
+            max_depth: DEFAULT_MAX_DEPTH,
+            dedup: false,
+            looseness: strict(),
+            contents_capacity: 0,
+            hooks: Hooks::default(),
+        }
+    }
+}
+
+impl<T: Float> QuadtreeBuilder<T> {
+    /// Returns a `QuadtreeBuilder` with the default settings.
+    ///
+    /// # Examples
+    /// ```
+    /// # use spatialize::quadtree::{Quadtree, QuadtreeBuilder};
+    /// let qt: Quadtree = QuadtreeBuilder::new().bounds(-1.0, 1.0, 2.0, 2.0).build();
+    /// assert_eq!((-1.0, 1.0, 2.0, 2.0), qt.bounds());
+    /// ```
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the boundaries of the `Quadtree`, given like in `Quadtree::new`.
+    pub fn bounds(mut self, position_x: T, position_y: T, width: T, height: T) -> Self {
+        self.bounds = (position_x, position_y, width, height);
+        self
+    }
+
+    /// Sets the number of objects each node holds before it subdivides.
+    pub fn capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity;
+        self
+    }
+
+    /// Sets the depth at which nodes stop subdividing.
+    pub fn max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// Sets whether `insert` rejects an object already stored in the `Quadtree` with
+    /// `QuadtreeError::AlreadyPresent`, like `insert_unique` does.
+    pub fn dedup(mut self, dedup: bool) -> Self {
+        self.dedup = dedup;
+        self
+    }
+
+    /// Makes the `Quadtree` loose, expanding the bounds of every node but the root around its
+    /// center by `factor`, e.g. to twice their width and height for a factor of `2`. Objects
+    /// straddling the center of a node then still move into the quadrant containing their own
+    /// center if they fit in its expanded bounds, instead of staying high up in the tree.
+    /// Queries search the expanded bounds. Factors below `1` are treated as `1`, which is
+    /// the default and keeps the `Quadtree` strict.
+    ///
+    /// # Examples
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use spatialize::quadtree::{Aabb, Bounded, Quadtree};
+    /// let depth_of_object = |qt: &Quadtree| {
+    ///     let mut object_depth = None;
+    ///     qt.visit_nodes(|node, depth| {
+    ///         if node.node_len() > 0 {
+    ///             object_depth = Some(depth);
+    ///         }
+    ///     });
+    ///     object_depth.unwrap()
+    /// };
+    /// // straddling the vertical center line of the root
+    /// let straddling = || Rc::new(Aabb::new(-1.0, 1.0, 2.0, 3.0));
+    ///
+    /// let mut strict = Quadtree::builder().bounds(-10.0, 10.0, 20.0, 20.0).build();
+    /// strict.insert(straddling()).unwrap();
+    /// assert_eq!(0, depth_of_object(&strict));
+    ///
+    /// let mut loose = Quadtree::builder()
+    ///     .bounds(-10.0, 10.0, 20.0, 20.0)
+    ///     .loose(2.0)
+    ///     .build();
+    /// loose.insert(straddling()).unwrap();
+    /// assert!(depth_of_object(&loose) > 0);
+    ///
+    /// // the part of the object outside the quadrant it is stored in is still found
+    /// let mut hits: Vec<Rc<dyn Bounded>> = vec![];
+    /// loose.query_point(-0.5, 2.0, &mut hits);
+    /// assert_eq!(1, hits.len());
+    /// ```
+    pub fn loose(mut self, factor: T) -> Self {
+        self.looseness = factor.max(strict());
+        self
+    }
+
+    /// Sets the number of objects the contents of the root node are allocated for, see
+    /// `Quadtree::reserve`.
+    pub fn contents_capacity(mut self, contents_capacity: usize) -> Self {
+        self.contents_capacity = contents_capacity;
+        self
+    }
+
+    /// Sets a hook called with every node right after it subdivides, before its objects are
+    /// redistributed into the new quadrants, e.g. to log where subdivisions happen.
+    ///
+    /// # Examples
+    /// ```
+    /// # use std::cell::Cell;
+    /// # use std::rc::Rc;
+    /// # use spatialize::quadtree::{Point, Quadtree};
+    /// let subdivisions = Rc::new(Cell::new(0));
+    /// let counter = Rc::clone(&subdivisions);
+    /// let mut qt = Quadtree::builder()
+    ///     .bounds(-10.0, 10.0, 20.0, 20.0)
+    ///     .capacity(1)
+    ///     .on_subdivide(move |_: &Quadtree| counter.set(counter.get() + 1))
+    ///     .build();
+    ///
+    /// qt.insert(Rc::new(Point::new(-5.0, 5.0))).unwrap();
+    /// assert_eq!(0, subdivisions.get());
+    /// qt.insert(Rc::new(Point::new(5.0, -5.0))).unwrap();
+    /// assert_eq!(1, subdivisions.get());
+    /// assert_eq!(qt.stats().node_count, 1 + 4 * subdivisions.get());
+    /// ```
+    pub fn on_subdivide<F: FnMut(&Quadtree<T>) + 'static>(mut self, hook: F) -> Self
+    where
+        T: 'static,
+    {
+        self.hooks.on_subdivide = Some(Rc::new(RefCell::new(hook)));
+        self
+    }
+
+    /// Sets a hook called with a node and an object whenever the object is stored in the
+    /// contents of that node, including objects kept in a node while it subdivides. Objects
+    /// moving on into a quadrant are only reported for the node they end up in.
+    ///
+    /// # Examples
+    /// ```
+    /// # use std::cell::RefCell;
+    /// # use std::rc::Rc;
+    /// # use spatialize::quadtree::{Bounded, Point, Quadtree};
+    /// let depths = Rc::new(RefCell::new(vec![]));
+    /// let log = Rc::clone(&depths);
+    /// let mut qt = Quadtree::builder()
+    ///     .bounds(-10.0, 10.0, 20.0, 20.0)
+    ///     .capacity(1)
+    ///     .max_depth(1)
+    ///     .on_store(move |node: &Quadtree, _: &Rc<dyn Bounded>| {
+    ///         log.borrow_mut().push(node.bounds())
+    ///     })
+    ///     .build();
+    ///
+    /// qt.insert(Rc::new(Point::new(-5.0, 5.0))).unwrap();
+    /// qt.insert(Rc::new(Point::new(5.0, -5.0))).unwrap();
+    /// assert_eq!(
+    ///     vec![
+    ///         (-10.0, 10.0, 20.0, 20.0),
+    ///         (-10.0, 10.0, 10.0, 10.0),
+    ///         (0.0, 0.0, 10.0, 10.0),
+    ///     ],
+    ///     *depths.borrow()
+    /// );
+    /// ```
+    pub fn on_store<F: FnMut(&Quadtree<T>, &Rc<dyn Bounded<T>>) + 'static>(
+        mut self,
+        hook: F,
+    ) -> Self
+    where
+        T: 'static,
+    {
+        self.hooks.on_store = Some(Rc::new(RefCell::new(hook)));
+        self
+    }
+
+    /// Returns a `Quadtree` configured with the settings of this builder.
+    pub fn build(self) -> Quadtree<T> {
+        let (position_x, position_y, width, height) = self.bounds;
+        let mut quadtree = Quadtree::with_limits(
+            position_x,
+            position_y,
+            width,
+            height,
+            self.capacity,
+            self.max_depth,
+        );
+        quadtree.dedup = self.dedup;
+        quadtree.looseness = self.looseness;
+        quadtree.hooks = self.hooks;
+        quadtree.reserve(self.contents_capacity);
+        quadtree
+    }
+}
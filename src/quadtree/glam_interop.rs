@@ -0,0 +1,88 @@
+//! Conversions between the types of `spatialize` and the `glam` vector types, available with
+//! the `glam` feature. They only move the coordinates and compile down to nothing.
+
+use alloc::rc::Rc;
+use alloc::vec::Vec;
+
+use glam::Vec2;
+
+use super::{Aabb, Bounded, Point, Quadtree};
+
+impl From<Vec2> for Point<f32> {
+    fn from(vec: Vec2) -> Self {
+        Point::new(vec.x, vec.y)
+    }
+}
+
+impl From<Point<f32>> for Vec2 {
+    fn from(point: Point<f32>) -> Self {
+        Vec2::new(point.x, point.y)
+    }
+}
+
+impl Aabb<f32> {
+    /// Returns an `Aabb` centered on `center` with the given `size`, i.e. its full width and
+    /// height. Requires the `glam` feature.
+    ///
+    /// # Examples
+    /// ```
+    /// # use glam::Vec2;
+    /// # use spatialize::quadtree::Aabb;
+    /// let aabb = Aabb::from_center_size(Vec2::new(1.0, 3.0), Vec2::new(4.0, 4.0));
+    /// assert_eq!(Aabb::new(-1.0, 1.0, 3.0, 5.0), aabb);
+    /// assert_eq!(Vec2::new(-1.0, 1.0), aabb.min());
+    /// assert_eq!(Vec2::new(3.0, 5.0), aabb.max());
+    /// ```
+    pub fn from_center_size(center: Vec2, size: Vec2) -> Self {
+        let min = center - size / 2.0;
+        let max = center + size / 2.0;
+        Self::new(min.x, min.y, max.x, max.y)
+    }
+
+    /// Returns the minimum corner of the `Aabb`. Requires the `glam` feature.
+    pub fn min(&self) -> Vec2 {
+        Vec2::new(self.min_x, self.min_y)
+    }
+
+    /// Returns the maximum corner of the `Aabb`. Requires the `glam` feature.
+    pub fn max(&self) -> Vec2 {
+        Vec2::new(self.max_x, self.max_y)
+    }
+}
+
+impl Quadtree<f32> {
+    /// Pushes every object whose edges enclose `point` into `out`, see `query_point`.
+    /// Requires the `glam` feature.
+    ///
+    /// # Examples
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use glam::Vec2;
+    /// # use spatialize::quadtree::{Aabb, Bounded, Quadtree};
+    /// let mut qt = Quadtree::new(-10.0, 10.0, 20.0, 20.0);
+    /// qt.insert(Rc::new(Aabb::from_center_size(Vec2::ZERO, Vec2::splat(2.0)))).unwrap();
+    /// let mut hits: Vec<Rc<dyn Bounded>> = vec![];
+    /// qt.query_point_vec2(Vec2::new(0.5, -0.5), &mut hits);
+    /// assert_eq!(1, hits.len());
+    /// ```
+    pub fn query_point_vec2(&self, point: Vec2, out: &mut Vec<Rc<dyn Bounded>>) {
+        self.query_point(point.x, point.y, out);
+    }
+
+    /// Returns up to `k` objects ordered by their distance from `point`, see `k_nearest`.
+    /// Requires the `glam` feature.
+    ///
+    /// # Examples
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use glam::Vec2;
+    /// # use spatialize::quadtree::{Point, Quadtree};
+    /// let mut qt = Quadtree::new(-10.0, 10.0, 20.0, 20.0);
+    /// qt.insert(Rc::new(Point::from(Vec2::new(1.0, 1.0)))).unwrap();
+    /// qt.insert(Rc::new(Point::from(Vec2::new(-5.0, 5.0)))).unwrap();
+    /// assert_eq!(1, qt.k_nearest_vec2(Vec2::ZERO, 1).len());
+    /// ```
+    pub fn k_nearest_vec2(&self, point: Vec2, k: usize) -> Vec<Rc<dyn Bounded>> {
+        self.k_nearest(point.x, point.y, k)
+    }
+}
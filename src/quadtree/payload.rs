@@ -0,0 +1,196 @@
+use alloc::collections::BTreeMap;
+use alloc::rc::Rc;
+use alloc::vec::Vec;
+
+use num_traits::Float;
+
+use super::{Bounded, Quadtree, QuadtreeError};
+
+/// A `Quadtree` storing a payload of type `P` along with each object, like an entity id or a
+/// label, so the geometry doesn't need to carry it.
+///
+/// Payloads are kept next to the tree, keyed by the address of the object they belong to, and
+/// queries return every matching object paired with a reference to its payload. The wrapped
+/// tree is available through `tree` for queries that don't need the payloads.
+///
+/// # Examples
+/// ```
+/// # use std::rc::Rc;
+/// # use spatialize::quadtree::{Aabb, Bounded, PayloadQuadtree};
+/// let mut qt = PayloadQuadtree::new(-10.0, 10.0, 20.0, 20.0);
+/// qt.insert(Rc::new(Aabb::new(0.0, -5.0, 5.0, 0.0)), "tree").unwrap();
+/// qt.insert(Rc::new(Aabb::new(-8.0, 6.0, -6.0, 8.0)), "rock").unwrap();
+/// qt.insert(Rc::new(Aabb::new(6.0, -8.0, 8.0, -6.0)), "chest").unwrap();
+///
+/// let rect_view = Aabb::new(-10.0, -1.0, 1.0, 10.0);
+/// let mut result_vec = vec![];
+/// qt.get_rect(&rect_view, &mut result_vec).unwrap();
+/// let mut labels: Vec<&str> = result_vec.iter().map(|(_, label)| **label).collect();
+/// labels.sort();
+/// assert_eq!(vec!["rock", "tree"], labels);
+/// ```
+#[derive(Debug)]
+pub struct PayloadQuadtree<P, T = f32> {
+    tree: Quadtree<T>,
+    payloads: BTreeMap<usize, P>,
+}
+
+/// A private function returning the address of `sized_object`, which identifies its payload.
+fn key<T>(sized_object: &Rc<dyn Bounded<T>>) -> usize {
+    Rc::as_ptr(sized_object) as *const () as usize
+}
+
+impl<P, T: Float> PayloadQuadtree<P, T> {
+    /// Returns a `PayloadQuadtree` with the specified boundaries and the `DEFAULT_CAPACITY`.
+    ///
+    /// # Examples
+    /// ```
+    /// # use spatialize::quadtree::PayloadQuadtree;
+    /// let qt: PayloadQuadtree<u32> = PayloadQuadtree::new(-100.0, 100.0, 200.0, 200.0);
+    /// ```
+    pub fn new(position_x: T, position_y: T, width: T, height: T) -> Self {
+        Self::from_quadtree(Quadtree::new(position_x, position_y, width, height))
+    }
+
+    /// Returns a `PayloadQuadtree` storing its objects in the empty `tree`, which keeps its
+    /// bounds and limits.
+    ///
+    /// # Examples
+    /// ```
+    /// # use spatialize::quadtree::{PayloadQuadtree, Quadtree};
+    /// let tree = Quadtree::with_capacity(-100.0, 100.0, 200.0, 200.0, 8);
+    /// let qt: PayloadQuadtree<u32> = PayloadQuadtree::from_quadtree(tree);
+    /// assert!(qt.is_empty());
+    /// ```
+    pub fn from_quadtree(mut tree: Quadtree<T>) -> Self {
+        tree.clear();
+        Self {
+            tree,
+            payloads: BTreeMap::new(),
+        }
+    }
+
+    /// Returns the `Quadtree` holding the objects, to run queries not involving the payloads.
+    pub fn tree(&self) -> &Quadtree<T> {
+        &self.tree
+    }
+
+    /// Inserts `sized_object` along with its `payload`. Returns `QuadtreeError::AlreadyPresent`
+    /// if the object is stored already, leaving its payload unchanged, or
+    /// `QuadtreeError::OutOfBounds` if it doesn't fit.
+    pub fn insert(
+        &mut self,
+        sized_object: Rc<dyn Bounded<T>>,
+        payload: P,
+    ) -> Result<(), QuadtreeError> {
+        let key = key(&sized_object);
+        if self.payloads.contains_key(&key) {
+            return Err(QuadtreeError::AlreadyPresent);
+        }
+        self.tree.insert(sized_object)?;
+        self.payloads.insert(key, payload);
+        Ok(())
+    }
+
+    /// Returns the payload of `sized_object`, or `None` if it isn't stored.
+    pub fn get(&self, sized_object: &Rc<dyn Bounded<T>>) -> Option<&P> {
+        self.payloads.get(&key(sized_object))
+    }
+
+    /// Returns a mutable reference to the payload of `sized_object`, or `None` if it isn't
+    /// stored.
+    pub fn get_mut(&mut self, sized_object: &Rc<dyn Bounded<T>>) -> Option<&mut P> {
+        self.payloads.get_mut(&key(sized_object))
+    }
+
+    /// Removes `sized_object` and returns its payload, or returns `QuadtreeError::NotFound` if
+    /// it isn't stored.
+    ///
+    /// # Examples
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use spatialize::quadtree::{Bounded, PayloadQuadtree, Point, QuadtreeError};
+    /// let mut qt = PayloadQuadtree::new(-10.0, 10.0, 20.0, 20.0);
+    /// let sized_object: Rc<dyn Bounded> = Rc::new(Point::new(1.0, 1.0));
+    /// qt.insert(Rc::clone(&sized_object), 7).unwrap();
+    /// *qt.get_mut(&sized_object).unwrap() += 1;
+    ///
+    /// assert_eq!(Ok(8), qt.remove(&sized_object));
+    /// assert_eq!(Err(QuadtreeError::NotFound), qt.remove(&sized_object));
+    /// assert!(qt.is_empty());
+    /// ```
+    pub fn remove(&mut self, sized_object: &Rc<dyn Bounded<T>>) -> Result<P, QuadtreeError> {
+        let key = key(sized_object);
+        if !self.payloads.contains_key(&key) {
+            return Err(QuadtreeError::NotFound);
+        }
+        self.tree.remove(sized_object)?;
+        self.payloads.remove(&key).ok_or(QuadtreeError::NotFound)
+    }
+
+    /// Removes all objects and their payloads, keeping the bounds and limits.
+    pub fn clear(&mut self) {
+        self.tree.clear();
+        self.payloads.clear();
+    }
+
+    /// Returns the number of stored objects.
+    pub fn len(&self) -> usize {
+        self.payloads.len()
+    }
+
+    /// Returns `true` if no objects are stored.
+    pub fn is_empty(&self) -> bool {
+        self.payloads.is_empty()
+    }
+
+    /// Pushes every object overlapping `rect` into `out` along with its payload, like
+    /// `Quadtree::get_rect`.
+    pub fn get_rect<'a>(
+        &'a self,
+        rect: &dyn Bounded<T>,
+        out: &mut Vec<(Rc<dyn Bounded<T>>, &'a P)>,
+    ) -> Result<(), QuadtreeError> {
+        self.tree
+            .for_each_in_rect(rect, |sized_object| out.extend(self.pair(sized_object)));
+        Ok(())
+    }
+
+    /// Pushes every object containing the point `(x, y)` into `out` along with its payload,
+    /// like `Quadtree::query_point`.
+    pub fn query_point<'a>(&'a self, x: T, y: T, out: &mut Vec<(Rc<dyn Bounded<T>>, &'a P)>) {
+        let mut found = Vec::new();
+        self.tree.query_point(x, y, &mut found);
+        out.extend(
+            found
+                .iter()
+                .filter_map(|sized_object| self.pair(sized_object)),
+        );
+    }
+
+    /// Returns the object closest to the point `(x, y)` along with its payload, like
+    /// `Quadtree::nearest`, or `None` if the tree is empty.
+    ///
+    /// # Examples
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use spatialize::quadtree::{PayloadQuadtree, Point};
+    /// let mut qt = PayloadQuadtree::new(-10.0, 10.0, 20.0, 20.0);
+    /// qt.insert(Rc::new(Point::new(1.0, 1.0)), "near").unwrap();
+    /// qt.insert(Rc::new(Point::new(8.0, -8.0)), "far").unwrap();
+    ///
+    /// assert_eq!(Some(&"near"), qt.nearest(0.0, 0.0).map(|(_, label)| label));
+    /// assert_eq!(Some(&"far"), qt.nearest(9.0, -9.0).map(|(_, label)| label));
+    /// ```
+    pub fn nearest(&self, x: T, y: T) -> Option<(Rc<dyn Bounded<T>>, &P)> {
+        self.tree
+            .nearest(x, y)
+            .and_then(|sized_object| self.pair(&sized_object))
+    }
+
+    /// A private function pairing `sized_object` with its payload.
+    fn pair(&self, sized_object: &Rc<dyn Bounded<T>>) -> Option<(Rc<dyn Bounded<T>>, &P)> {
+        self.get(sized_object)
+            .map(|payload| (Rc::clone(sized_object), payload))
+    }
+}
@@ -0,0 +1,53 @@
+use core::fmt::Debug;
+
+use num_traits::Float;
+
+use super::Bounded;
+
+/// A point implementing the `Bounded` trait as a box without an area, where all four edges
+/// lie on the point's coordinates.
+///
+/// # Examples
+/// ```
+/// use std::rc::Rc;
+/// use spatialize::quadtree::{Point, Quadtree};
+///
+/// let mut qt = Quadtree::new(-10.0, 10.0, 20.0, 20.0);
+/// qt.insert(Rc::new(Point::new(1.0, 2.0))).unwrap();
+/// assert_eq!(1, qt.len());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Point<T = f32> {
+    pub x: T,
+    pub y: T,
+}
+
+impl<T> Point<T> {
+    /// Returns a `Point` at `(x, y)`.
+    ///
+    /// # Examples
+    /// ```
+    /// # use spatialize::quadtree::Point;
+    /// let point = Point::new(1.0, 2.0);
+    /// assert_eq!(2.0, point.y);
+    /// ```
+    pub fn new(x: T, y: T) -> Self {
+        Self { x, y }
+    }
+}
+
+impl<T: Float + Debug> Bounded<T> for Point<T> {
+    fn north_edge(&self) -> T {
+        self.y
+    }
+    fn east_edge(&self) -> T {
+        self.x
+    }
+    fn south_edge(&self) -> T {
+        self.y
+    }
+    fn west_edge(&self) -> T {
+        self.x
+    }
+}
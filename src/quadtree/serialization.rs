@@ -0,0 +1,75 @@
+//! Serialization of a `Quadtree` with `serde`, enabled by the `serde` feature.
+//!
+//! The bounds, limits and structure of every node are serialized as they are. Stored objects
+//! are trait objects, so only their edges are serialized, and a deserialized `Quadtree`
+//! contains an `Aabb` with the same edges in place of each original object. Queries on a
+//! round-tripped tree therefore return objects with the same edges as on the original.
+//!
+//! # Examples
+//! ```
+//! # use std::rc::Rc;
+//! # use spatialize::quadtree::{Aabb, Bounded, Quadtree};
+//! let mut qt = Quadtree::with_capacity(-10.0, 10.0, 20.0, 20.0, 1);
+//! qt.insert(Rc::new(Aabb::new(0.0, -5.0, 5.0, 0.0))).unwrap();
+//! qt.insert(Rc::new(Aabb::new(-8.0, 6.0, -6.0, 8.0))).unwrap();
+//! qt.insert(Rc::new(Aabb::new(-1.0, -1.0, 1.0, 1.0))).unwrap();
+//!
+//! let json = serde_json::to_string(&qt).unwrap();
+//! let restored: Quadtree = serde_json::from_str(&json).unwrap();
+//!
+//! let edges = |qt: &Quadtree| {
+//!     let rect_view = Aabb::new(-10.0, 1.0, -1.0, 10.0);
+//!     let mut result_vec: Vec<Rc<dyn Bounded>> = vec![];
+//!     qt.get_rect(&rect_view, &mut result_vec).unwrap();
+//!     result_vec
+//!         .iter()
+//!         .map(|rc| [rc.north_edge(), rc.east_edge(), rc.south_edge(), rc.west_edge()])
+//!         .collect::<Vec<_>>()
+//! };
+//! assert_eq!(2, edges(&qt).len());
+//! assert_eq!(edges(&qt), edges(&restored));
+//! ```
+
+use alloc::rc::Rc;
+use alloc::vec::Vec;
+use core::fmt::Debug;
+
+use num_traits::Float;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use super::{Aabb, Contents, Entry};
+
+/// Serializes the contents of a node as `[north, east, south, west]` edges. Dropped weakly
+/// referenced objects are skipped, and all objects are deserialized as strong references.
+pub(super) mod contents {
+    use super::*;
+
+    pub fn serialize<T, S>(contents: &[Entry<T>], serializer: S) -> Result<S::Ok, S::Error>
+    where
+        T: Float + Serialize,
+        S: Serializer,
+    {
+        serializer.collect_seq(contents.iter().filter_map(Entry::get).map(|rc| {
+            [
+                rc.north_edge(),
+                rc.east_edge(),
+                rc.south_edge(),
+                rc.west_edge(),
+            ]
+        }))
+    }
+
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<Contents<T>, D::Error>
+    where
+        T: Float + Debug + Deserialize<'de> + 'static,
+        D: Deserializer<'de>,
+    {
+        let edges = Vec::<[T; 4]>::deserialize(deserializer)?;
+        Ok(edges
+            .into_iter()
+            .map(|[north, east, south, west]| {
+                Entry::strong(Rc::new(Aabb::new(west, south, east, north)))
+            })
+            .collect())
+    }
+}
@@ -0,0 +1,499 @@
+use std::sync::{Arc, PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+use num_traits::Float;
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+
+use super::{
+    encloses_point, object_distance_squared, out_of_bounds, two, Bounded, Edges, QuadtreeError,
+    DEFAULT_CAPACITY, DEFAULT_MAX_DEPTH,
+};
+
+/// An object stored in a `SyncQuadtree`, which can be shared between threads.
+pub type SharedBounded<T = f32> = Arc<dyn Bounded<T> + Send + Sync>;
+
+/// A thread-safe variant of the `Quadtree`, storing its quadrants behind `Arc<RwLock<...>>`
+/// and its objects as `SharedBounded`, so the tree is `Send + Sync` when `T` is.
+///
+/// Queries take `&self` and only acquire read locks, so a built tree can be queried from many
+/// threads at once. Writes like `insert` take `&mut self`, so sharing a tree that is still
+/// being modified requires exclusive coordination, e.g. by wrapping it in a `RwLock`.
+///
+/// # Examples
+/// ```
+/// # use std::sync::Arc;
+/// # use spatialize::quadtree::{Aabb, Bounded, SyncQuadtree};
+/// let mut qt = SyncQuadtree::new(-10.0, 10.0, 20.0, 20.0);
+/// qt.insert(Arc::new(Aabb::new(0.0, -5.0, 5.0, 0.0))).unwrap();
+/// qt.insert(Arc::new(Aabb::new(-8.0, 6.0, -6.0, 8.0))).unwrap();
+///
+/// std::thread::scope(|scope| {
+///     for _ in 0..4 {
+///         scope.spawn(|| {
+///             let mut result_vec = vec![];
+///             qt.get_rect(&Aabb::new(-10.0, -10.0, 10.0, 10.0), &mut result_vec)
+///                 .unwrap();
+///             assert_eq!(2, result_vec.len());
+///         });
+///     }
+/// });
+/// ```
+#[derive(Debug)]
+pub struct SyncQuadtree<T = f32> {
+    position_x: T,
+    position_y: T,
+    width: T,
+    height: T,
+    capacity: usize,
+    depth: usize,
+    max_depth: usize,
+    divided: bool,
+    northeast_quad: Option<Arc<RwLock<Self>>>,
+    northwest_quad: Option<Arc<RwLock<Self>>>,
+    southeast_quad: Option<Arc<RwLock<Self>>>,
+    southwest_quad: Option<Arc<RwLock<Self>>>,
+    contents: Vec<SharedBounded<T>>,
+}
+
+/// The depth up to which `par_query_rect` queries the quadrants of a node in parallel. Nodes
+/// at this depth or deeper are traversed serially, which bounds the number of tasks to
+/// 4^`PARALLEL_DEPTH` and keeps the overhead of spawning them away from small subtrees.
+#[cfg(feature = "rayon")]
+const PARALLEL_DEPTH: usize = 4;
+
+/// A private function acquiring a read lock, ignoring poisoning as readers never leave a
+/// node in an inconsistent state.
+fn read<T>(lock: &RwLock<T>) -> RwLockReadGuard<'_, T> {
+    lock.read().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// A private function acquiring a write lock, ignoring poisoning.
+fn write<T>(lock: &RwLock<T>) -> RwLockWriteGuard<'_, T> {
+    lock.write().unwrap_or_else(PoisonError::into_inner)
+}
+
+impl<T: Float> SyncQuadtree<T> {
+    /// Returns a `SyncQuadtree` with the specified boundaries and the `DEFAULT_CAPACITY`.
+    ///
+    /// # Examples
+    /// ```
+    /// # use spatialize::quadtree::SyncQuadtree;
+    /// let qt: SyncQuadtree = SyncQuadtree::new(-100.0, 100.0, 200.0, 200.0);
+    /// ```
+    pub fn new(position_x: T, position_y: T, width: T, height: T) -> Self {
+        Self::with_capacity(position_x, position_y, width, height, DEFAULT_CAPACITY)
+    }
+
+    /// Returns a `SyncQuadtree` with the specified boundaries, where each node holds up to
+    /// `capacity` objects before it subdivides.
+    ///
+    /// # Examples
+    /// ```
+    /// # use spatialize::quadtree::SyncQuadtree;
+    /// let qt: SyncQuadtree = SyncQuadtree::with_capacity(-100.0, 100.0, 200.0, 200.0, 8);
+    /// ```
+    pub fn with_capacity(
+        position_x: T,
+        position_y: T,
+        width: T,
+        height: T,
+        capacity: usize,
+    ) -> Self {
+        Self::with_limits(
+            position_x,
+            position_y,
+            width,
+            height,
+            capacity,
+            DEFAULT_MAX_DEPTH,
+        )
+    }
+
+    /// Returns a `SyncQuadtree` with the specified boundaries, node `capacity` and `max_depth`.
+    ///
+    /// # Examples
+    /// ```
+    /// # use spatialize::quadtree::SyncQuadtree;
+    /// let qt: SyncQuadtree = SyncQuadtree::with_limits(-100.0, 100.0, 200.0, 200.0, 8, 10);
+    /// ```
+    pub fn with_limits(
+        position_x: T,
+        position_y: T,
+        width: T,
+        height: T,
+        capacity: usize,
+        max_depth: usize,
+    ) -> Self {
+        Self {
+            position_x,
+            position_y,
+            width,
+            height,
+            capacity,
+            depth: 0,
+            max_depth,
+            divided: false,
+            northeast_quad: None,
+            northwest_quad: None,
+            southeast_quad: None,
+            southwest_quad: None,
+            contents: vec![],
+        }
+    }
+
+    /// A private function returning the existing quadrants in the order
+    /// northeast, northwest, southeast, southwest.
+    fn quadrants(&self) -> impl DoubleEndedIterator<Item = &Arc<RwLock<Self>>> {
+        [
+            &self.northeast_quad,
+            &self.northwest_quad,
+            &self.southeast_quad,
+            &self.southwest_quad,
+        ]
+        .into_iter()
+        .flatten()
+    }
+
+    /// A private function checking whether `rect` overlaps the bounds of this node.
+    fn intersects(&self, rect: &dyn Bounded<T>) -> bool {
+        !(rect.north_edge() < self.position_y - self.height
+            || rect.east_edge() < self.position_x
+            || rect.south_edge() > self.position_y
+            || rect.west_edge() > self.position_x + self.width)
+    }
+
+    /// A private function checking whether `sized_object` lies within the bounds of this node.
+    fn fits(&self, sized_object: &dyn Bounded<T>) -> bool {
+        sized_object.north_edge() <= self.position_y
+            && sized_object.east_edge() <= self.position_x + self.width
+            && sized_object.south_edge() >= self.position_y - self.height
+            && sized_object.west_edge() >= self.position_x
+    }
+
+    /// A private function creating a quadrant of half this node's size at the given position.
+    fn quadrant(&self, position_x: T, position_y: T) -> Arc<RwLock<Self>> {
+        let mut quadrant = SyncQuadtree::with_limits(
+            position_x,
+            position_y,
+            self.width / two(),
+            self.height / two(),
+            self.capacity,
+            self.max_depth,
+        );
+        quadrant.depth = self.depth + 1;
+        Arc::new(RwLock::new(quadrant))
+    }
+
+    /// A private function used to partition the `SyncQuadtree` into four quadrants.
+    fn subdivide(&mut self) {
+        if !self.divided {
+            let half_width = self.width / two();
+            let half_height = self.height / two();
+            self.northeast_quad =
+                Some(self.quadrant(self.position_x + half_width, self.position_y));
+            self.northwest_quad = Some(self.quadrant(self.position_x, self.position_y));
+            self.southeast_quad =
+                Some(self.quadrant(self.position_x + half_width, self.position_y - half_height));
+            self.southwest_quad =
+                Some(self.quadrant(self.position_x, self.position_y - half_height));
+            self.divided = true;
+
+            for sized_object in std::mem::take(&mut self.contents) {
+                self.insert_into_quadrants(sized_object);
+            }
+        }
+    }
+
+    /// A private function inserting `sized_object` into the first quadrant it fits in,
+    /// keeping it in this node if it doesn't fit in any of them.
+    fn insert_into_quadrants(&mut self, sized_object: SharedBounded<T>) {
+        // lower and then left quadrants win on boundaries, like in the `Quadtree`
+        for lock in self.quadrants().rev() {
+            if write(lock).insert(Arc::clone(&sized_object)).is_ok() {
+                return;
+            }
+        }
+        self.contents.push(sized_object);
+    }
+
+    /// Inserts an object implementing the `Bounded` trait.
+    ///
+    /// # Examples
+    /// ```
+    /// # use std::sync::Arc;
+    /// # use spatialize::quadtree::{Aabb, Bounded, SyncQuadtree};
+    /// let mut qt = SyncQuadtree::new(-10.0, 10.0, 20.0, 20.0);
+    /// assert!(qt.insert(Arc::new(Aabb::new(0.0, -5.0, 5.0, 0.0))).is_ok());
+    /// ```
+    pub fn insert(&mut self, sized_object: SharedBounded<T>) -> Result<(), QuadtreeError> {
+        if self.fits(&*sized_object) {
+            if self.divided {
+                self.insert_into_quadrants(sized_object);
+            } else {
+                self.contents.push(sized_object);
+                if self.contents.len() > self.capacity && self.depth < self.max_depth {
+                    self.subdivide();
+                }
+            }
+            Ok(())
+        } else {
+            let bounds = Edges {
+                north: self.position_y,
+                east: self.position_x + self.width,
+                south: self.position_y - self.height,
+                west: self.position_x,
+            };
+            Err(out_of_bounds(&*sized_object, &bounds))
+        }
+    }
+
+    /// Removes an object previously inserted into the `SyncQuadtree`, identified by pointer
+    /// equality.
+    ///
+    /// # Examples
+    /// ```
+    /// # use std::sync::Arc;
+    /// # use spatialize::quadtree::{Aabb, Bounded, QuadtreeError, SharedBounded, SyncQuadtree};
+    /// let mut qt = SyncQuadtree::new(-10.0, 10.0, 20.0, 20.0);
+    /// let sized_object: SharedBounded = Arc::new(Aabb::new(0.0, -5.0, 5.0, 0.0));
+    /// qt.insert(Arc::clone(&sized_object)).unwrap();
+    /// assert!(qt.remove(&sized_object).is_ok());
+    /// assert_eq!(Err(QuadtreeError::NotFound), qt.remove(&sized_object));
+    /// ```
+    pub fn remove(&mut self, sized_object: &SharedBounded<T>) -> Result<(), QuadtreeError> {
+        if self.fits(&**sized_object) {
+            if let Some(index) = self
+                .contents
+                .iter()
+                .position(|arc| Arc::ptr_eq(arc, sized_object))
+            {
+                self.contents.remove(index);
+                return Ok(());
+            }
+            for lock in self.quadrants() {
+                if write(lock).remove(sized_object).is_ok() {
+                    return Ok(());
+                }
+            }
+        }
+        Err(QuadtreeError::NotFound)
+    }
+
+    /// Removes every object and quadrant, keeping the bounds, capacity and maximum depth.
+    ///
+    /// # Examples
+    /// ```
+    /// # use std::sync::Arc;
+    /// # use spatialize::quadtree::{Aabb, Bounded, SyncQuadtree};
+    /// let mut qt = SyncQuadtree::new(-10.0, 10.0, 20.0, 20.0);
+    /// qt.insert(Arc::new(Aabb::new(0.0, -5.0, 5.0, 0.0))).unwrap();
+    /// qt.clear();
+    /// assert!(qt.is_empty());
+    /// ```
+    pub fn clear(&mut self) {
+        self.contents.clear();
+        self.northeast_quad = None;
+        self.northwest_quad = None;
+        self.southeast_quad = None;
+        self.southwest_quad = None;
+        self.divided = false;
+    }
+
+    /// Returns the number of objects stored across all nodes of the `SyncQuadtree`.
+    ///
+    /// # Examples
+    /// ```
+    /// # use std::sync::Arc;
+    /// # use spatialize::quadtree::{Aabb, Bounded, SyncQuadtree};
+    /// let mut qt = SyncQuadtree::new(-10.0, 10.0, 20.0, 20.0);
+    /// qt.insert(Arc::new(Aabb::new(0.0, -5.0, 5.0, 0.0))).unwrap();
+    /// assert_eq!(1, qt.len());
+    /// ```
+    pub fn len(&self) -> usize {
+        self.contents.len() + self.quadrants().map(|lock| read(lock).len()).sum::<usize>()
+    }
+
+    /// Returns `true` if no objects are stored in the `SyncQuadtree`.
+    ///
+    /// # Examples
+    /// ```
+    /// # use spatialize::quadtree::SyncQuadtree;
+    /// let qt: SyncQuadtree = SyncQuadtree::new(-10.0, 10.0, 20.0, 20.0);
+    /// assert!(qt.is_empty());
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        self.contents.is_empty() && self.quadrants().all(|lock| read(lock).is_empty())
+    }
+
+    /// Calls `f` for every object overlapping `rect`.
+    ///
+    /// # Examples
+    /// ```
+    /// # use std::sync::Arc;
+    /// # use spatialize::quadtree::{Aabb, Bounded, SyncQuadtree};
+    /// let mut qt = SyncQuadtree::new(-10.0, 10.0, 20.0, 20.0);
+    /// qt.insert(Arc::new(Aabb::new(0.0, -5.0, 5.0, 0.0))).unwrap();
+    /// let mut count = 0;
+    /// qt.for_each_in_rect(&Aabb::new(-2.0, -8.0, 8.0, 2.0), |_| count += 1);
+    /// assert_eq!(1, count);
+    /// ```
+    pub fn for_each_in_rect<F: FnMut(&SharedBounded<T>)>(&self, rect: &dyn Bounded<T>, mut f: F) {
+        self.visit_rect(rect, &mut f);
+    }
+
+    /// A private function doing the traversal of `for_each_in_rect`.
+    fn visit_rect<F: FnMut(&SharedBounded<T>)>(&self, rect: &dyn Bounded<T>, f: &mut F) {
+        if self.intersects(rect) {
+            for lock in self.quadrants() {
+                read(lock).visit_rect(rect, f);
+            }
+            for arc in self.contents.iter() {
+                if arc.intersects(rect) {
+                    f(arc);
+                }
+            }
+        }
+    }
+
+    /// Pushes every object overlapping `rect` into `vec`. A `rect` outside the bounds of the
+    /// `SyncQuadtree` has no results and still returns `Ok`.
+    ///
+    /// # Examples
+    /// ```
+    /// # use std::sync::Arc;
+    /// # use spatialize::quadtree::{Aabb, Bounded, SyncQuadtree};
+    /// let mut qt = SyncQuadtree::new(-10.0, 10.0, 20.0, 20.0);
+    /// qt.insert(Arc::new(Aabb::new(0.0, -5.0, 5.0, 0.0))).unwrap();
+    /// let mut result_vec = vec![];
+    /// qt.get_rect(&Aabb::new(-2.0, -8.0, 8.0, 2.0), &mut result_vec).unwrap();
+    /// assert_eq!(1, result_vec.len());
+    /// ```
+    pub fn get_rect(
+        &self,
+        rect: &dyn Bounded<T>,
+        vec: &mut Vec<SharedBounded<T>>,
+    ) -> Result<(), QuadtreeError> {
+        self.for_each_in_rect(rect, |arc| vec.push(Arc::clone(arc)));
+        Ok(())
+    }
+
+    /// Pushes every object overlapping `rect` into `out` like `get_rect`, but queries the four
+    /// quadrants of each node in parallel using rayon. Nodes at depth 4 or deeper, as well as
+    /// undivided nodes, fall back to the serial traversal, so small trees gain nothing over
+    /// `get_rect`. Objects are pushed in the same order as `get_rect` would push them.
+    ///
+    /// Requires the `rayon` feature.
+    ///
+    /// # Examples
+    /// ```
+    /// # use std::sync::Arc;
+    /// # use spatialize::quadtree::{Aabb, Bounded, SyncQuadtree};
+    /// let mut qt = SyncQuadtree::with_capacity(-100.0, 100.0, 200.0, 200.0, 4);
+    /// for i in 0..100 {
+    ///     let offset = i as f32 * 1.5 - 75.0;
+    ///     qt.insert(Arc::new(Aabb::new(offset, offset - 1.0, offset + 1.0, offset))).unwrap();
+    /// }
+    /// let view = Aabb::new(-50.0, -50.0, 50.0, 50.0);
+    /// let mut parallel = vec![];
+    /// let mut serial = vec![];
+    /// qt.par_query_rect(&view, &mut parallel);
+    /// qt.get_rect(&view, &mut serial).unwrap();
+    /// assert_eq!(serial.len(), parallel.len());
+    /// assert!(serial.iter().zip(parallel.iter()).all(|(a, b)| Arc::ptr_eq(a, b)));
+    /// ```
+    #[cfg(feature = "rayon")]
+    pub fn par_query_rect(&self, rect: &(dyn Bounded<T> + Sync), out: &mut Vec<SharedBounded<T>>)
+    where
+        T: Send + Sync,
+    {
+        if self.intersects(rect) {
+            if self.depth >= PARALLEL_DEPTH || !self.divided {
+                self.for_each_in_rect(rect, |arc| out.push(Arc::clone(arc)));
+                return;
+            }
+            let quadrants: Vec<&Arc<RwLock<Self>>> = self.quadrants().collect();
+            let results: Vec<Vec<SharedBounded<T>>> = quadrants
+                .into_par_iter()
+                .map(|lock| {
+                    let mut found = vec![];
+                    read(lock).par_query_rect(rect, &mut found);
+                    found
+                })
+                .collect();
+            results.into_iter().for_each(|found| out.extend(found));
+            for arc in self.contents.iter() {
+                if arc.intersects(rect) {
+                    out.push(Arc::clone(arc));
+                }
+            }
+        }
+    }
+
+    /// Pushes every object whose edges lie within `radius` of the point `(x, y)` into `out`.
+    ///
+    /// # Examples
+    /// ```
+    /// # use std::sync::Arc;
+    /// # use spatialize::quadtree::{Aabb, Bounded, SyncQuadtree};
+    /// let mut qt = SyncQuadtree::new(-10.0, 10.0, 20.0, 20.0);
+    /// qt.insert(Arc::new(Aabb::new(1.0, 0.0, 2.0, 1.0))).unwrap();
+    /// qt.insert(Arc::new(Aabb::new(4.0, -5.0, 5.0, -4.0))).unwrap();
+    /// let mut in_circle = vec![];
+    /// qt.query_circle(0.0, 0.0, 5.0, &mut in_circle);
+    /// assert_eq!(1, in_circle.len());
+    /// ```
+    pub fn query_circle(&self, x: T, y: T, radius: T, out: &mut Vec<SharedBounded<T>>) {
+        let radius_squared = radius * radius;
+        let distance_squared = super::distance_squared(
+            self.position_y,
+            self.position_x + self.width,
+            self.position_y - self.height,
+            self.position_x,
+            x,
+            y,
+        );
+        if distance_squared <= radius_squared {
+            for lock in self.quadrants() {
+                read(lock).query_circle(x, y, radius, out);
+            }
+            for arc in self.contents.iter() {
+                if object_distance_squared(&**arc, x, y) <= radius_squared {
+                    out.push(Arc::clone(arc));
+                }
+            }
+        }
+    }
+
+    /// Pushes every object whose edges enclose the point `(x, y)` into `out`.
+    ///
+    /// # Examples
+    /// ```
+    /// # use std::sync::Arc;
+    /// # use spatialize::quadtree::{Aabb, Bounded, SyncQuadtree};
+    /// let mut qt = SyncQuadtree::new(-10.0, 10.0, 20.0, 20.0);
+    /// qt.insert(Arc::new(Aabb::new(-5.0, -5.0, 5.0, 5.0))).unwrap();
+    /// qt.insert(Arc::new(Aabb::new(1.0, 2.0, 3.0, 4.0))).unwrap();
+    /// let mut hits = vec![];
+    /// qt.query_point(2.0, 3.0, &mut hits);
+    /// assert_eq!(2, hits.len());
+    /// ```
+    pub fn query_point(&self, x: T, y: T, out: &mut Vec<SharedBounded<T>>) {
+        if encloses_point(
+            self.position_y,
+            self.position_x + self.width,
+            self.position_y - self.height,
+            self.position_x,
+            x,
+            y,
+        ) {
+            for lock in self.quadrants() {
+                read(lock).query_point(x, y, out);
+            }
+            for arc in self.contents.iter() {
+                if arc.contains_point(x, y) {
+                    out.push(Arc::clone(arc));
+                }
+            }
+        }
+    }
+}